@@ -0,0 +1,82 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use neohub::protocol::{decode_frame, decode_response, encode_response, Frame};
+use neohub::LiveData;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+// time parsing a GET_LIVE_DATA frame, as for a large install: the test data's devices,
+// repeated; `bench-live-data [devices] [iterations]`
+
+// just each device as a map, for comparison: parsing live data buffers each device much
+// like this, to collect `Device::extra`
+#[derive(Deserialize)]
+struct Buffered {
+    devices: Vec<Map<String, Value>>,
+}
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let devices: usize = args.next().map_or(Ok(200), |arg| arg.parse())?;
+    let iterations: u32 = args.next().map_or(Ok(200), |arg| arg.parse())?;
+
+    let mut live_data: Value = serde_json::from_str(include_str!("../tests/live-data-1.json"))?;
+    let templates = live_data["devices"].as_array().cloned().unwrap_or_default();
+    live_data["devices"] = templates
+        .iter()
+        .cycle()
+        .take(devices)
+        .enumerate()
+        .map(|(i, device)| {
+            let mut device = device.clone();
+            device["ZONE_NAME"] = json!(format!("Zone {}", i));
+            device
+        })
+        .collect();
+    let frame = encode_response(&json!(1), "hub", &live_data.to_string());
+    println!(
+        "{} devices, {} KB frame, {} iterations",
+        devices,
+        frame.len() / 1024,
+        iterations
+    );
+
+    report("frame", iterations, || {
+        black_box(decode_frame(frame.as_bytes()));
+    });
+    let Frame::Response(resp) = decode_frame(frame.as_bytes()) else {
+        anyhow::bail!("the frame didn't decode");
+    };
+    report("live data, via a Value", iterations, || {
+        let value: Value = serde_json::from_str(&resp.response).unwrap();
+        black_box(serde_json::from_value::<LiveData>(value).unwrap());
+    });
+    report("live data", iterations, || {
+        black_box(decode_response::<LiveData>("GET_LIVE_DATA", &resp.response).unwrap());
+    });
+    report("devices, buffered", iterations, || {
+        black_box(
+            serde_json::from_str::<Buffered>(&resp.response)
+                .unwrap()
+                .devices,
+        );
+    });
+    Ok(())
+}
+
+fn report(name: &str, iterations: u32, mut f: impl FnMut()) {
+    // warm up
+    f();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let each = start.elapsed() / iterations;
+    println!("{:>24}: {:>8.3} ms", name, as_millis(each));
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -1,11 +1,97 @@
 use anyhow::{bail, Context, Result};
+use rustyline::completion::{Completer, Pair};
 use rustyline::config::Configurer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
 use serde_json::Value;
 
+// completes command names, then zone names
+struct Complete {
+    zones: Vec<String>,
+}
+
+impl Completer for Complete {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let (start, prefix, options): (usize, &str, Vec<&str>) = match line.split_once(' ') {
+            None => (0, line, neohub::commands::ALL.to_vec()),
+            Some((command, arg)) => (
+                command.len() + 1,
+                arg,
+                self.zones.iter().map(String::as_str).collect(),
+            ),
+        };
+        let candidates = options
+            .into_iter()
+            .filter(|option| option.starts_with(prefix))
+            .map(|option| Pair {
+                display: option.to_string(),
+                replacement: option.to_string(),
+            })
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for Complete {
+    type Hint = String;
+}
+
+impl Highlighter for Complete {}
+
+impl Validator for Complete {}
+
+impl rustyline::Helper for Complete {}
+
+// completes the first argument with command names
+fn completion_script(shell: &str) -> Result<String> {
+    let commands = neohub::commands::ALL.join(" ");
+    Ok(match shell {
+        "bash" => format!("complete -W \"{commands}\" neohub-cli\n"),
+        "zsh" => format!("#compdef neohub-cli\n_arguments '1:command:({commands})'\n"),
+        "fish" => format!("complete -c neohub-cli -f -n __fish_is_first_arg -a \"{commands}\"\n"),
+        _ => bail!("unsupported shell {:?}, expected bash, zsh or fish", shell),
+    })
+}
+
+async fn run(client: &neohub::Client, command: &str) -> Result<Value> {
+    // the argument is everything after the first space, so it can be a zone name
+    Ok(match command.trim().split_once(' ') {
+        None => client.command_void(command.trim()).await?,
+        Some((command, arg)) => client.command_str(command, arg.trim()).await?,
+    })
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     pretty_env_logger::init();
-    let mut client = neohub::Client::from_env()?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let [flag, shell] = args.as_slice() {
+        if flag == "--completions" {
+            print!("{}", completion_script(shell)?);
+            return Ok(());
+        }
+    }
+
+    let client = neohub::Client::from_env()?;
+
+    // one-shot, e.g. `neohub-cli GET_LIVE_DATA`
+    if !args.is_empty() {
+        let result = run(&client, &args.join(" ")).await?;
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        client.disconnect().await?;
+        return Ok(());
+    }
+
     println!("Attempting to connect...");
     println!(
         "{:?}",
@@ -14,20 +100,25 @@ async fn main() -> Result<()> {
             .await
             .with_context(|| "initial connection failed")?
     );
-    let mut rl = rustyline::DefaultEditor::new()?;
+    let zones = client
+        .zones_map()
+        .await?
+        .names()
+        .map(str::to_owned)
+        .collect();
+    let mut rl = rustyline::Editor::<Complete, DefaultHistory>::new()?;
+    rl.set_helper(Some(Complete { zones }));
     rl.set_auto_add_history(true);
     loop {
         let command = rl.readline(">> ")?;
         if command.is_empty() {
             break;
         };
-        let parts = command.split(' ').collect::<Vec<_>>();
-        let result: Value = match parts.len() {
-            1 => client.command_void(parts[0]).await?,
-            2 => client.command_str(parts[0], parts[1]).await?,
-            _ => bail!("unable to handle multiple arguments"),
-        };
-        println!("{}", serde_json::to_string_pretty(&result)?);
+        match run(&client, &command).await {
+            Ok(result) => println!("{}", serde_json::to_string_pretty(&result)?),
+            // keep the session going
+            Err(e) => println!("error: {:#}", e),
+        }
     }
 
     client.disconnect().await?;
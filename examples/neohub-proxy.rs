@@ -0,0 +1,25 @@
+use anyhow::{bail, Context, Result};
+use log::info;
+use tokio::net::TcpListener;
+
+// share one hub connection: configure the hub as for `from_env`, then point other tools
+// at ws://<this machine>:4243 with NEOHUB_PROXY_TOKEN as their token. Only local tools
+// can connect unless NEOHUB_PROXY_LISTEN says otherwise; the token is then required, as
+// it gives full control of the hub, and is sent in the clear.
+#[tokio::main]
+async fn main() -> Result<()> {
+    pretty_env_logger::init();
+    let listen = std::env::var("NEOHUB_PROXY_LISTEN").unwrap_or("127.0.0.1:4243".to_string());
+    let listener = TcpListener::bind(&listen).await?;
+    let token = match std::env::var("NEOHUB_PROXY_TOKEN") {
+        Ok(token) if !token.is_empty() => token,
+        _ if !listener.local_addr()?.ip().is_loopback() => {
+            bail!("NEOHUB_PROXY_TOKEN must be set to listen on {}", listen)
+        }
+        // local tools already have the hub's token
+        _ => std::env::var("NEOHUB_TOKEN").context("NEOHUB_PROXY_TOKEN isn't set")?,
+    };
+    let server = neohub::Server::new(neohub::Client::from_env()?, token);
+    info!("listening on {}", listen);
+    server.serve(listener).await
+}
@@ -0,0 +1,237 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use log::warn;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::{DeviceRef, LiveData, Poller};
+
+/// What a [`Rule`] is looking for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// The temperature is below this.
+    Below(f64),
+    /// The temperature is above this.
+    Above(f64),
+    Offline,
+    LowBattery,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// Only this zone, or every zone.
+    pub zone: Option<String>,
+    pub condition: Condition,
+    /// How long the condition must hold before the alert is raised.
+    pub duration: Duration,
+}
+
+impl Rule {
+    pub fn below(zone: impl ToString, temperature: f64, duration: Duration) -> Rule {
+        Rule {
+            zone: Some(zone.to_string()),
+            condition: Condition::Below(temperature),
+            duration,
+        }
+    }
+
+    pub fn above(zone: impl ToString, temperature: f64, duration: Duration) -> Rule {
+        Rule {
+            zone: Some(zone.to_string()),
+            condition: Condition::Above(temperature),
+            duration,
+        }
+    }
+
+    /// Any device, sensor or repeater going offline.
+    pub fn offline(duration: Duration) -> Rule {
+        Rule {
+            zone: None,
+            condition: Condition::Offline,
+            duration,
+        }
+    }
+
+    /// Any device or sensor reporting a low battery.
+    pub fn low_battery() -> Rule {
+        Rule {
+            zone: None,
+            condition: Condition::LowBattery,
+            duration: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertState {
+    Raised,
+    Cleared,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// Index of the rule, in the order they were added.
+    pub rule: usize,
+    pub zone: String,
+    pub state: AlertState,
+    /// The zone's temperature, for temperature rules.
+    pub temperature: Option<f64>,
+}
+
+#[derive(Default)]
+struct Tracked {
+    // when the condition started holding
+    since: Option<Instant>,
+    raised: bool,
+}
+
+/// Evaluates [`Rule`]s against successive [`LiveData`] snapshots.
+///
+/// Temperature alerts only clear once the temperature has recovered past the threshold by
+/// the hysteresis, so a zone hovering around the threshold doesn't flap.
+pub struct Alerts {
+    rules: Vec<Rule>,
+    hysteresis: f64,
+    state: HashMap<(usize, String), Tracked>,
+}
+
+impl Default for Alerts {
+    fn default() -> Self {
+        Alerts {
+            rules: Vec::new(),
+            hysteresis: 0.5,
+            state: HashMap::new(),
+        }
+    }
+}
+
+impl Alerts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Degrees, default 0.5.
+    pub fn hysteresis(mut self, hysteresis: f64) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Update with a new snapshot, taken at `now`, returning any changes in alert state.
+    pub fn observe(&mut self, live_data: &LiveData, now: Instant) -> Vec<Alert> {
+        let mut changes = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            let devices = live_data
+                .all_devices()
+                .filter(|device| rule.zone.as_ref().is_none_or(|z| z == device.zone_name()));
+            for device in devices {
+                let temperature = device.temperature();
+                let tracked = self
+                    .state
+                    .entry((index, device.zone_name().to_string()))
+                    .or_default();
+                let holds = matches(rule, device, temperature, tracked.raised, self.hysteresis);
+
+                let state = match (holds, tracked.raised) {
+                    (true, false) => {
+                        let since = *tracked.since.get_or_insert(now);
+                        if now.duration_since(since) < rule.duration {
+                            continue;
+                        }
+                        tracked.raised = true;
+                        AlertState::Raised
+                    }
+                    (false, true) => {
+                        tracked.raised = false;
+                        tracked.since = None;
+                        AlertState::Cleared
+                    }
+                    (false, false) => {
+                        tracked.since = None;
+                        continue;
+                    }
+                    (true, true) => continue,
+                };
+                changes.push(Alert {
+                    rule: index,
+                    zone: device.zone_name().to_string(),
+                    state,
+                    temperature: match rule.condition {
+                        Condition::Below(_) | Condition::Above(_) => temperature,
+                        _ => None,
+                    },
+                });
+            }
+        }
+
+        // zones which have gone, e.g. been removed from the hub, clear their alerts
+        let zones: HashSet<_> = live_data.all_devices().map(DeviceRef::zone_name).collect();
+        self.state.retain(|(rule, zone), tracked| {
+            if zones.contains(zone.as_str()) {
+                return true;
+            }
+            if tracked.raised {
+                changes.push(Alert {
+                    rule: *rule,
+                    zone: zone.clone(),
+                    state: AlertState::Cleared,
+                    temperature: None,
+                });
+            }
+            false
+        });
+        changes
+    }
+
+    /// Evaluate each of `poller`'s snapshots, sending alert changes to the returned channel.
+    ///
+    /// The task stops when the receiver, or the poller, is dropped. Snapshots missed by
+    /// falling behind are skipped.
+    pub fn spawn(mut self, poller: &Poller) -> (mpsc::Receiver<Alert>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel(32);
+        let mut snapshots = poller.subscribe();
+        let task = tokio::spawn(async move {
+            loop {
+                let snapshot = match snapshots.recv().await {
+                    Ok(snapshot) => snapshot,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("alerts: skipped {} snapshots", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                for alert in self.observe(&snapshot.live_data, Instant::now()) {
+                    if tx.send(alert).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        (rx, task)
+    }
+}
+
+// once raised, a temperature alert needs to recover past the hysteresis to clear
+fn matches(
+    rule: &Rule,
+    device: DeviceRef<'_>,
+    temperature: Option<f64>,
+    raised: bool,
+    hysteresis: f64,
+) -> bool {
+    let margin = if raised { hysteresis } else { 0.0 };
+    match rule.condition {
+        Condition::Below(threshold) => temperature.is_some_and(|t| t < threshold + margin),
+        Condition::Above(threshold) => temperature.is_some_and(|t| t > threshold - margin),
+        Condition::Offline => device.offline(),
+        Condition::LowBattery => device.low_battery(),
+    }
+}
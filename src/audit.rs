@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+pub(crate) type OnCommand = dyn Fn(&CommandRecord) + Send + Sync;
+
+/// A command the client was asked to send, see [`crate::Builder::on_command`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommandRecord {
+    /// e.g. `SET_TEMP`, if the message was well-formed enough to tell.
+    pub command: Option<String>,
+    /// The whole message, including arguments, e.g. `{'SET_TEMP':[20,['Office']]}`.
+    pub message: String,
+    pub outcome: Outcome,
+    pub latency: Duration,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Sent, and the hub responded.
+    Sent,
+    /// Not sent, as the client is in dry-run mode.
+    DryRun,
+    /// Not sent: the client is read-only, the command is dangerous (see
+    /// [`crate::Builder::allow_dangerous`]), or the message isn't a single command.
+    Refused,
+    /// Sending failed, or the response was unusable.
+    Failed(String),
+}
@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{commands, profiles, Client, EngineersData, Profile, ScheduleFormat, Zones};
+
+/// Everything we know how to read back from a hub, see [`Client::backup`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub firmware_version: Option<String>,
+    pub zones: Zones,
+    pub profiles: BTreeMap<String, Profile>,
+    // GET_PROFILE_0, by zone; the format isn't documented, so these aren't restored
+    pub comfort_levels: BTreeMap<String, Value>,
+    pub engineers: BTreeMap<String, EngineersData>,
+    // GET_SYSTEM
+    pub system: Value,
+}
+
+/// A write which [`Client::restore`] would make (or has made).
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Change {
+    pub description: String,
+    pub command: &'static str,
+    pub args: Value,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct RestoreReport {
+    pub changes: Vec<Change>,
+    /// Differences we can't fix, e.g. zones which need to be re-paired.
+    pub warnings: Vec<String>,
+}
+
+// system settings we know how to write back: (GET_SYSTEM key, command)
+const SYSTEM_SETTINGS: &[(&str, &str)] = &[
+    ("CORF", commands::SET_TEMP_FORMAT),
+    ("TIME_ZONE", commands::TIME_ZONE),
+];
+
+impl Client {
+    /// Read the hub's configuration into a single serializable snapshot.
+    pub async fn backup(&self) -> Result<Backup> {
+        Ok(Backup {
+            firmware_version: self.identify().await?.firmware_version,
+            zones: self.zones_map().await.context("reading zones")?,
+            profiles: self.profiles().await.context("reading profiles")?,
+            comfort_levels: self
+                .command_void(commands::GET_PROFILE_0)
+                .await
+                .context("reading comfort levels")?,
+            engineers: self.engineers().await.context("reading engineers data")?,
+            system: self
+                .command_void(commands::GET_SYSTEM)
+                .await
+                .context("reading system settings")?,
+        })
+    }
+
+    /// Write a [`Backup`] back to the hub, only changing what differs.
+    ///
+    /// With `dry_run`, nothing is written, and the report shows what would be.
+    pub async fn restore(&self, backup: &Backup, dry_run: bool) -> Result<RestoreReport> {
+        let current = self.backup().await?;
+        let report = current.plan_restore(backup)?;
+        if dry_run {
+            return Ok(report);
+        }
+
+        for change in &report.changes {
+            debug!("restoring: {}", change.description);
+            self.command_result(change.command, &change.args)
+                .await
+                .with_context(|| format!("restoring: {}", change.description))?;
+        }
+        Ok(report)
+    }
+}
+
+impl Backup {
+    /// What restoring `wanted` over this (e.g. the hub's current state) would change, see
+    /// [`Client::restore`].
+    pub fn plan_restore(&self, wanted: &Backup) -> Result<RestoreReport> {
+        plan_restore(self, wanted)
+    }
+}
+
+fn plan_restore(current: &Backup, wanted: &Backup) -> Result<RestoreReport> {
+    let mut report = RestoreReport::default();
+
+    for name in wanted.zones.names() {
+        if current.zones.id(name).is_none() {
+            report.warnings.push(format!(
+                "zone {name:?} is missing, and must be paired again"
+            ));
+        }
+    }
+
+    for (name, profile) in &wanted.profiles {
+        // the backup's id may since have been given to another profile, so overwrite the
+        // profile with this name, or store a new one
+        let existing = current.profiles.get(name);
+        let profile = Profile {
+            profile_id: existing.map_or(0, |existing| existing.profile_id),
+            ..profile.clone()
+        };
+        let unchanged = match existing {
+            Some(existing) => serde_json::to_value(existing)? == serde_json::to_value(&profile)?,
+            None => false,
+        };
+        if !unchanged {
+            report.changes.push(Change {
+                description: format!("store profile {name:?}"),
+                command: commands::STORE_PROFILE2,
+                args: profiles::store_args(&profile),
+            });
+        }
+    }
+
+    for (zone, levels) in &wanted.comfort_levels {
+        if current.comfort_levels.get(zone) != Some(levels) {
+            report.warnings.push(format!(
+                "comfort levels of {zone:?} differ, and must be set by hand"
+            ));
+        }
+    }
+
+    for (zone, wanted) in &wanted.engineers {
+        let Some(current) = current.engineers.get(zone) else {
+            continue;
+        };
+        let settings = [
+            (
+                "preheat",
+                commands::SET_PREHEAT,
+                current.max_preheat,
+                wanted.max_preheat,
+            ),
+            (
+                "switching differential",
+                commands::SET_DIFF,
+                current.switching_differential,
+                wanted.switching_differential,
+            ),
+            (
+                "output delay",
+                commands::SET_DELAY,
+                current.output_delay,
+                wanted.output_delay,
+            ),
+            (
+                "floor limit",
+                commands::SET_FLOOR,
+                current.floor_limit,
+                wanted.floor_limit,
+            ),
+        ];
+        for (setting, command, current, wanted) in settings {
+            if current != wanted {
+                report.changes.push(Change {
+                    description: format!("set {setting} of {zone:?} from {current} to {wanted}"),
+                    command,
+                    args: json!([wanted, [zone]]),
+                });
+            }
+        }
+    }
+
+    for (key, command) in SYSTEM_SETTINGS {
+        let wanted = &wanted.system[key];
+        if !wanted.is_null() && &current.system[key] != wanted {
+            report.changes.push(Change {
+                description: format!("set {key} to {wanted}"),
+                command,
+                args: wanted.clone(),
+            });
+        }
+    }
+
+    // GET_SYSTEM may report the format as a number, which SET_FORMAT doesn't take
+    let format = |backup: &Backup| {
+        let format = backup.system.get("FORMAT")?;
+        Some(serde_json::from_value::<ScheduleFormat>(format.clone()))
+    };
+    match (format(current), format(wanted)) {
+        (_, None) => {}
+        (_, Some(Err(e))) => report.warnings.push(format!("unknown FORMAT: {e}")),
+        (Some(Ok(current)), Some(Ok(wanted))) if current == wanted => {}
+        (_, Some(Ok(wanted))) => {
+            let args = serde_json::to_value(wanted)?;
+            report.changes.push(Change {
+                description: format!("set FORMAT to {args}"),
+                command: commands::SET_FORMAT,
+                args,
+            });
+        }
+    }
+
+    Ok(report)
+}
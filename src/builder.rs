@@ -0,0 +1,251 @@
+use std::future::Future;
+use std::net::{Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use url::Url;
+
+use crate::{
+    Client, CommandRecord, ConnectionEvent, Generation, Middleware, Opts, TemperatureUnit,
+    Transport, WebSocketConfig,
+};
+
+/// Configure a [`Client`], see [`Client::builder`].
+#[derive(Clone)]
+pub struct Builder {
+    url: String,
+    token: String,
+    opts: Opts,
+}
+
+impl Builder {
+    pub(crate) fn new(url: impl ToString, token: impl ToString) -> Self {
+        Builder {
+            url: url.to_string(),
+            token: token.to_string(),
+            opts: Opts::default(),
+        }
+    }
+
+    /// Like [`Client::from_env`], for further configuration.
+    pub fn from_env() -> Result<Self> {
+        crate::env::builder_from_env()
+    }
+
+    /// How long to wait for each command, default 15s.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.opts.timeout = timeout;
+        self
+    }
+
+    /// Log commands which would change the hub's state, and pretend they succeeded,
+    /// instead of sending them. Read-only commands are sent as usual.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.opts.dry_run = dry_run;
+        self
+    }
+
+    /// Refuse to send any command which could change the hub's state.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.opts.read_only = read_only;
+        self
+    }
+
+    /// Allow commands which can take the hub offline or undo installation work, like
+    /// rebooting it or removing zones; see [`Client::danger_zone`]. Off by default, so
+    /// they can't be sent by accident.
+    pub fn allow_dangerous(mut self, allow_dangerous: bool) -> Self {
+        self.opts.allow_dangerous = allow_dangerous;
+        self
+    }
+
+    /// Call `on_command` after every command, whether it was sent or not; e.g. for an audit log.
+    pub fn on_command(
+        mut self,
+        on_command: impl Fn(&CommandRecord) + Send + Sync + 'static,
+    ) -> Self {
+        self.opts.on_command = Some(Arc::new(on_command));
+        self
+    }
+
+    /// Call `on_event` when the client connects, loses its connection, or retries; e.g. to
+    /// log or alert on connectivity, without polling [`Client::is_connected`].
+    ///
+    /// This is called from whichever task notices, so it shouldn't block.
+    pub fn on_event(mut self, on_event: impl Fn(ConnectionEvent) + Send + Sync + 'static) -> Self {
+        self.opts.on_event = Some(Arc::new(on_event));
+        self
+    }
+
+    /// Fail typed calls (e.g. [`Client::live_data`]) if the hub returns fields this crate
+    /// doesn't know about, naming them. Useful for noticing firmware changes.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.opts.strict = strict;
+        self
+    }
+
+    /// Skip detecting the hub's generation, see [`Client::generation`].
+    pub fn generation(mut self, generation: Generation) -> Self {
+        self.opts.generation = Some(generation);
+        self
+    }
+
+    /// Skip detecting the hub's temperature unit, see [`Client::temperature_unit`].
+    pub fn temperature_unit(mut self, unit: TemperatureUnit) -> Self {
+        self.opts.temperature_unit = Some(unit);
+        self
+    }
+
+    /// Wrap sending each message in `middleware`. The first added is the outermost.
+    ///
+    /// Middleware runs after the dry-run and read-only checks, i.e. only for messages
+    /// which would actually be sent.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.opts.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    // before any other middleware
+    pub(crate) fn outer_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.opts.middleware.insert(0, middleware);
+        self
+    }
+
+    /// Tune the websocket, e.g. its buffer and message size limits; by default, messages
+    /// and frames are limited to 8MiB, see [`Builder::max_message_size`].
+    ///
+    /// The underlying websocket library doesn't support `permessage-deflate`, so responses
+    /// can't be compressed.
+    pub fn websocket_config(mut self, config: WebSocketConfig) -> Self {
+        self.opts.websocket_config = Some(config);
+        self
+    }
+
+    /// Refuse responses (and websocket frames) larger than `bytes`, rather than buffering
+    /// them; `None` allows any size.
+    ///
+    /// The default, 8MiB, is several times the live data of a large install.
+    pub fn max_message_size(mut self, bytes: impl Into<Option<usize>>) -> Self {
+        let bytes = bytes.into();
+        let config = self
+            .opts
+            .websocket_config
+            .get_or_insert_with(crate::default_websocket_config);
+        config.max_message_size = bytes;
+        config.max_frame_size = bytes;
+        self
+    }
+
+    /// Connect through a proxy, `http://[user:password@]host:port` (using CONNECT) or
+    /// `socks5://[user:password@]host:port`; use `socks5h://` to have the proxy look up the
+    /// hub's host name, rather than looking it up here.
+    pub fn proxy(mut self, url: impl ToString) -> Self {
+        self.opts.proxy = Some(url.to_string());
+        self
+    }
+
+    /// Connect to `address`, instead of looking up the url's host, e.g. for split-horizon dns.
+    ///
+    /// The url's host is still used for TLS and the websocket handshake.
+    pub fn address(mut self, address: SocketAddr) -> Self {
+        self.opts.address = Some(address);
+        self
+    }
+
+    /// Try connecting up to `attempts` times, waiting `backoff` after the first failure,
+    /// doubling each time. By default, there's a single attempt.
+    pub fn connect_retry(mut self, attempts: u32, backoff: Duration) -> Self {
+        self.opts.connect_attempts = Some(attempts.max(1));
+        self.opts.connect_backoff = backoff;
+        self
+    }
+
+    /// Before sending a command on a connection which has been idle for longer than `idle`,
+    /// check it's still alive (a pong within `deadline`), and reconnect if it isn't.
+    ///
+    /// Otherwise, the first command after e.g. the hub's restarted, or a NAT has forgotten us,
+    /// tends to fail with a timeout.
+    pub fn liveness_check(mut self, idle: Duration, deadline: Duration) -> Self {
+        self.opts.liveness_idle = Some(idle);
+        self.opts.liveness_deadline = deadline;
+        self
+    }
+
+    /// Connect with `connect`, rather than a websocket to the url, calling it again to
+    /// reconnect; e.g. to test against an in-memory hub.
+    pub fn transport<F, Fut, T>(mut self, connect: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+        T: Transport,
+    {
+        self.opts.transport = Some(Arc::new(move || {
+            let connecting = connect();
+            Box::pin(async move { Ok(Box::new(connecting.await?) as Box<dyn Transport>) })
+        }));
+        self
+    }
+
+    /// Keep the hub's last `frames` responses (by default, none), for debugging; see
+    /// [`Client::raw_frames`]. Responses which can't be parsed are always kept, see
+    /// [`Client::last_raw_response`].
+    pub fn retain_frames(mut self, frames: usize) -> Self {
+        self.opts.retain_frames = frames;
+        self
+    }
+
+    /// Like [`Builder::build`], for when the hub may not be reachable yet, e.g. at service
+    /// startup: connecting on the first command is tried 5 times (from a 1s backoff), unless
+    /// [`Builder::connect_retry`] says otherwise.
+    ///
+    /// Clients never connect until they're used, see [`Client::connect`].
+    pub fn build_lazy(mut self) -> Result<Client> {
+        self.opts.connect_attempts.get_or_insert(5);
+        self.build()
+    }
+
+    pub fn build(self) -> Result<Client> {
+        if let Some(proxy) = &self.opts.proxy {
+            let parsed = Url::parse(proxy).with_context(|| anyhow!("parsing proxy {:?}", proxy))?;
+            ensure!(
+                matches!(parsed.scheme(), "http" | "socks5" | "socks5h"),
+                "unsupported proxy scheme {:?}, expected http, socks5 or socks5h",
+                parsed.scheme()
+            );
+        }
+        Client::new_opts(self.url, self.token, self.opts)
+    }
+}
+
+// the hub's websocket api port
+const DEFAULT_PORT: u16 = 4243;
+
+pub(crate) fn host_url(host: &str, port: Option<u16>) -> Result<String> {
+    let host = host.trim();
+    ensure!(!host.is_empty(), "hub host is empty");
+    ensure!(
+        !host.contains("://"),
+        "expected a host, like 192.168.1.10, not a url: {:?}",
+        host
+    );
+    ensure!(
+        !host.contains(['/', '?', '#', '@']) && !host.contains(char::is_whitespace),
+        "expected a host, like 192.168.1.10: {:?}",
+        host
+    );
+    ensure!(
+        !host.contains('%'),
+        "ipv6 zone ids (like fe80::1%eth0) aren't supported, use a global address: {:?}",
+        host
+    );
+    // ipv6 addresses must be bracketed in urls
+    let host = if host.parse::<Ipv6Addr>().is_ok() {
+        format!("[{host}]")
+    } else {
+        host.to_string()
+    };
+    let url = format!("wss://{}:{}", host, port.unwrap_or(DEFAULT_PORT));
+    Url::parse(&url).with_context(|| anyhow!("invalid hub host {:?}", host))?;
+    Ok(url)
+}
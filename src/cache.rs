@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use crate::commands::{self, System};
+use crate::{Client, EngineersData, Profile, Recipe, Timestamps, Zones};
+
+/// Profiles, engineers data, recipes, zones and system settings, only refetched when the
+/// hub's [`Timestamps`] say they've changed.
+///
+/// Checking the timestamps costs a `GET_LIVE_DATA`; if you're already polling that,
+/// pass each one to [`Cache::observe`], and the cache won't fetch it again. Firmware
+/// which doesn't report [`Timestamps::system`] has its system settings fetched every time.
+#[derive(Default, Debug, Clone)]
+pub struct Cache {
+    timestamps: Option<Timestamps>,
+    profiles: Option<Cached<BTreeMap<String, Profile>>>,
+    engineers: Option<Cached<BTreeMap<String, EngineersData>>>,
+    recipes: Option<Cached<Vec<Recipe>>>,
+    zones: Option<Cached<Zones>>,
+    system: Option<Cached<System>>,
+}
+
+#[derive(Debug, Clone)]
+struct Cached<T> {
+    stamp: i64,
+    value: T,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use these timestamps for the next lookup, instead of fetching them.
+    pub fn observe(&mut self, live_data: &crate::LiveData) {
+        self.timestamps = Some(live_data.timestamps());
+    }
+
+    /// Forget everything; the next lookups will refetch.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub async fn profiles(&mut self, client: &Client) -> Result<&BTreeMap<String, Profile>> {
+        let stamp = self.timestamps(client).await?.profile_comfort_levels;
+        if !is_fresh(&self.profiles, stamp) {
+            let value = client.profiles().await?;
+            self.profiles = Some(Cached { stamp, value });
+        }
+        Ok(&self.profiles.as_ref().expect("just filled").value)
+    }
+
+    pub async fn engineers(&mut self, client: &Client) -> Result<&BTreeMap<String, EngineersData>> {
+        let stamp = self.timestamps(client).await?.engineers;
+        if !is_fresh(&self.engineers, stamp) {
+            let value = client.engineers().await?;
+            self.engineers = Some(Cached { stamp, value });
+        }
+        Ok(&self.engineers.as_ref().expect("just filled").value)
+    }
+
+    pub async fn recipes(&mut self, client: &Client) -> Result<&[Recipe]> {
+        let stamp = self.timestamps(client).await?.recipes;
+        if !is_fresh(&self.recipes, stamp) {
+            let value = client.recipes().await?;
+            self.recipes = Some(Cached { stamp, value });
+        }
+        Ok(&self.recipes.as_ref().expect("just filled").value)
+    }
+
+    pub async fn zones(&mut self, client: &Client) -> Result<&Zones> {
+        let stamp = self.timestamps(client).await?.device_lists;
+        if !is_fresh(&self.zones, stamp) {
+            let value = client.zones_map().await?;
+            self.zones = Some(Cached { stamp, value });
+        }
+        Ok(&self.zones.as_ref().expect("just filled").value)
+    }
+
+    /// `GET_SYSTEM`.
+    pub async fn system(&mut self, client: &Client) -> Result<&System> {
+        let stamp = self.timestamps(client).await?.system;
+        if !stamp.is_some_and(|stamp| is_fresh(&self.system, stamp)) {
+            let value = client.command_void(commands::GET_SYSTEM).await?;
+            self.system = Some(Cached {
+                stamp: stamp.unwrap_or_default(),
+                value,
+            });
+        }
+        Ok(&self.system.as_ref().expect("just filled").value)
+    }
+
+    // observed timestamps are used once, so a later lookup sees later changes
+    async fn timestamps(&mut self, client: &Client) -> Result<Timestamps> {
+        match self.timestamps.take() {
+            Some(timestamps) => Ok(timestamps),
+            None => Ok(client.live_data().await?.timestamps()),
+        }
+    }
+}
+
+fn is_fresh<T>(cached: &Option<Cached<T>>, stamp: i64) -> bool {
+    cached.as_ref().is_some_and(|cached| cached.stamp == stamp)
+}
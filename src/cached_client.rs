@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use log::debug;
+
+use crate::protocol::command_name;
+use crate::{error, Builder, Client, Middleware, Next};
+
+/// A [`Client`] which reuses responses to some read commands, e.g. `GET_ZONES`, for a
+/// while, so that different parts of an application don't each ask the hub.
+///
+/// Everything else is as for the wrapped [`Client`]. Responses are cached separately for
+/// each argument, and nothing is invalidated automatically: call
+/// [`CachedClient::invalidate`] after changing the hub.
+pub struct CachedClient {
+    client: Client,
+    cache: Arc<ResponseCache>,
+}
+
+struct ResponseCache {
+    ttls: HashMap<String, Duration>,
+    entries: Mutex<Entries>,
+}
+
+// responses, and when they were fetched, by message
+type Entries = HashMap<String, (Instant, (String, String))>;
+
+impl CachedClient {
+    /// Cache responses to each command for its ttl, e.g.
+    /// `[(commands::GET_ZONES, Duration::from_secs(60))]`.
+    ///
+    /// The cache is outside any [`Builder::middleware`], so cached responses skip it.
+    pub fn new(
+        builder: Builder,
+        ttls: impl IntoIterator<Item = (impl ToString, Duration)>,
+    ) -> Result<Self> {
+        let cache = Arc::new(ResponseCache {
+            ttls: ttls
+                .into_iter()
+                .map(|(command, ttl)| (command.to_string(), ttl))
+                .collect(),
+            entries: Mutex::default(),
+        });
+        let client = builder.outer_middleware(cache.clone()).build()?;
+        Ok(CachedClient { client, cache })
+    }
+
+    /// Forget all cached responses.
+    pub fn invalidate(&self) {
+        self.cache.lock().clear();
+    }
+
+    /// Forget cached responses to `command`, whatever their arguments.
+    pub fn invalidate_command(&self, command: &str) {
+        self.cache
+            .lock()
+            .retain(|msg, _| command_name(msg) != Some(command));
+    }
+
+    pub fn into_inner(self) -> Client {
+        self.client
+    }
+}
+
+impl Deref for CachedClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl ResponseCache {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        // nothing can panic while holding the lock
+        self.entries.lock().expect("cache poisoned")
+    }
+}
+
+impl Middleware for ResponseCache {
+    fn handle<'a>(
+        &'a self,
+        msg: &'a str,
+        mut next: Next<'a>,
+    ) -> BoxFuture<'a, Result<(String, String)>> {
+        Box::pin(async move {
+            let command = command_name(msg).unwrap_or_default();
+            let Some(ttl) = self.ttls.get(command) else {
+                return next.run(msg).await;
+            };
+            if let Some((fetched, resp)) = self.lock().get(msg) {
+                if fetched.elapsed() < *ttl {
+                    debug!("cached: {}", msg);
+                    return Ok(resp.clone());
+                }
+            }
+            let resp = next.run(msg).await?;
+            if error::rejection(command, &resp.1).is_none() {
+                self.lock()
+                    .insert(msg.to_string(), (Instant::now(), resp.clone()));
+            }
+            Ok(resp)
+        })
+    }
+}
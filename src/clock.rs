@@ -0,0 +1,134 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::thermostat::parse_hours_minutes;
+use crate::{Client, Day};
+
+const DAY: i64 = 24 * 3600;
+const QUARTER_HOUR: i64 = 15 * 60;
+
+/// The hub's offset from UTC, as its thermostats' clocks show it; see [`Client::time_zone`].
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeZone {
+    pub offset_seconds: i32,
+}
+
+/// A unix time, in the hub's time zone; displays like `2022-09-13T21:43:03+01:00`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HubDateTime {
+    /// As the hub reported it.
+    pub unix: i64,
+    pub zone: TimeZone,
+}
+
+impl TimeZone {
+    pub const UTC: TimeZone = TimeZone { offset_seconds: 0 };
+
+    /// The offset in effect at unix time `utc`, when a clock showed `local` (`H:MM`) on `day`
+    /// (live data's `DATE`, e.g. "tuesday"), to the nearest quarter hour; offsets are taken to
+    /// be between -12:00 and +14:00. Where both -12:00..-10:00 and +12:00..+14:00 fit the
+    /// time, `day` decides.
+    ///
+    /// `GET_SYSTEM`'s `TIME_ZONE` is only the standard offset: `DST_ON` is whether the hub
+    /// adjusts for daylight saving (see [`crate::commands::DST_ON`]), not whether it's in effect.
+    pub fn observed(day: &str, local: &str, utc: i64) -> Result<Self> {
+        let day: Day = day.parse()?;
+        let local = parse_hours_minutes(local)?.as_secs() as i64;
+        let offset = (local - utc.rem_euclid(DAY) + DAY / 2).rem_euclid(DAY) - DAY / 2;
+        let offset = (offset + QUARTER_HOUR / 2).div_euclid(QUARTER_HOUR) * QUARTER_HOUR;
+        // rounding can take -11:55 up to +12:00
+        let offset = if offset >= DAY / 2 {
+            offset - DAY
+        } else {
+            offset
+        };
+        let weekday =
+            |offset: i64| Day::ALL[((utc + offset).div_euclid(DAY) + 3).rem_euclid(7) as usize];
+        let offset = if offset <= -10 * 3600 && weekday(offset + DAY) == day {
+            offset + DAY
+        } else {
+            offset
+        };
+        Ok(TimeZone {
+            offset_seconds: offset as i32,
+        })
+    }
+
+    pub fn at(self, unix: i64) -> HubDateTime {
+        HubDateTime { unix, zone: self }
+    }
+}
+
+impl HubDateTime {
+    pub fn system_time(&self) -> SystemTime {
+        let offset = Duration::from_secs(self.unix.unsigned_abs());
+        if self.unix >= 0 {
+            UNIX_EPOCH + offset
+        } else {
+            UNIX_EPOCH - offset
+        }
+    }
+}
+
+impl fmt::Display for HubDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let local = self.unix + i64::from(self.zone.offset_seconds);
+        let (year, month, day) = civil_from_days(local.div_euclid(86400));
+        let seconds = local.rem_euclid(86400);
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )?;
+        let offset = self.zone.offset_seconds;
+        if offset == 0 {
+            return write!(f, "Z");
+        }
+        let sign = if offset < 0 { '-' } else { '+' };
+        let offset = offset.unsigned_abs() / 60;
+        write!(f, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+    }
+}
+
+// days since 1970-01-01 to (year, month, day); Howard Hinnant's `civil_from_days`
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64)
+}
+
+impl Client {
+    /// The hub's time zone, for converting times in live data: the offset between an online
+    /// thermostat's clock and the hub's.
+    pub async fn time_zone(&self) -> Result<TimeZone> {
+        let live_data = self.live_data().await?;
+        let device = live_data
+            .devices
+            .iter()
+            .find(|device| !device.offline)
+            .context("no online thermostats to read the time from")?;
+        TimeZone::observed(&device.date, &device.time, live_data.hub_time())
+            .with_context(|| format!("reading {:?}'s clock", device.zone_name))
+    }
+}
@@ -1,3 +1,13 @@
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    EngineersData, FanSpeed, HcMode, LiveData, Profile, ScheduleFormat, TemperatureUnit, Zones,
+};
+
 pub const ADVANCE: &str = "ADVANCE";
 pub const ALLOW_DOWNGRADE: &str = "ALLOW_DOWNGRADE";
 pub const AUTO_MODE_OFF: &str = "AUTO_MODE_OFF";
@@ -146,3 +156,483 @@ pub const GET_PROFILE: &str = "GET_PROFILE";
 pub const RUN_PROFILE: &str = "RUN_PROFILE";
 #[deprecated]
 pub const GET_PROFILE_NAMES: &str = "GET_PROFILE_NAMES";
+
+/// Every (non-deprecated) command, e.g. for completion.
+pub const ALL: &[&str] = &[
+    ADVANCE,
+    ALLOW_DOWNGRADE,
+    AUTO_MODE_OFF,
+    AWAY_OFF,
+    AWAY_ON,
+    BATTERY_SAVE_OFF,
+    BATTERY_SAVE_ON,
+    BOOST_OFF,
+    BOOST_ON,
+    CANCEL_HGROUP,
+    CANCEL_HOLD_ALL,
+    CANCEL_HOLIDAY,
+    CLEAR_CLOSE_DELAY,
+    CLEAR_COOLBOX,
+    CLEAR_CURRENT_PROFILE,
+    CLEAR_DEVICE_LIST,
+    CLEAR_OPEN_DELAY,
+    CLEAR_PROFILE,
+    CLEAR_PROFILE_ID,
+    COOL,
+    COOLBOX_MODE,
+    COOL_DEFAULT,
+    COPY_DCB,
+    CREATE_GROUP,
+    DELETE_GROUP,
+    DELETE_RECIPE,
+    DETACH_DEVICE,
+    DISABLE_MODE_CHANGE,
+    DOWNLOAD_INSTALL,
+    DST_OFF,
+    DST_ON,
+    EXPIRE_TOKEN,
+    EXTENDED_HISTORY,
+    FIRMWARE,
+    FROST_OFF,
+    FROST_ON,
+    GET_DATE,
+    GET_DEVICES,
+    GET_DEVICE_LIST,
+    GET_ENGINEERS,
+    GET_GROUPS,
+    GET_HOLD,
+    GET_HOLIDAY,
+    GET_HOURSRUN,
+    GET_LIVE_DATA,
+    GET_OEM_SETUP,
+    GET_PROFILES,
+    GET_PROFILE_0,
+    GET_PROFILE_TIMERS,
+    GET_RECIPES,
+    GET_SYSTEM,
+    GET_TEMPLOG,
+    GET_TIMER_0,
+    GET_TOKENS,
+    GET_ZONES,
+    GLOBAL_DEV_LIST,
+    GLOBAL_SYSTEM_TYPE,
+    HOLD,
+    HOLIDAY,
+    IDENTIFY,
+    IDENTIFY_DEV,
+    LEGACY_PORT,
+    LINK_DEVICE,
+    LOCK,
+    MANUAL_DST,
+    MANUAL_OF,
+    MANUAL_ON,
+    NAME_TOKEN,
+    NTP_OFF,
+    NTP_ON,
+    OFFLINE_DEVICES,
+    PERMIT_JOIN,
+    PROFILE_TITLE,
+    READ_COMFORT_LEVELS,
+    READ_DCB,
+    READ_TIMECLOCK,
+    REMOVE_REPEATER,
+    REMOVE_ZONE,
+    RESET,
+    RESET_HOMEKIT,
+    RESET_NCP,
+    RUN_PROFILE_ID,
+    RUN_RECIPE,
+    SET_AUTO_ON,
+    SET_BATTERY_SAVE_DELAY,
+    SET_CHANNEL,
+    SET_CLOSE_DELAY,
+    SET_COMFORT_LEVELS,
+    SET_COOL_TEMP,
+    SET_DATE,
+    SET_DELAY,
+    SET_DEW_POINT,
+    SET_DIFF,
+    SET_FAILSAFE,
+    SET_FAN_SPEED,
+    SET_FLOOR,
+    SET_FLOOR_LIMIT,
+    SET_FORMAT,
+    SET_FROST,
+    SET_GLOBAL_HC_MODE,
+    SET_HC_MODE,
+    SET_LEVEL_4,
+    SET_LEVEL_6,
+    SET_OPEN_DELAY,
+    SET_PREHEAT,
+    SET_PUMP_DELAY,
+    SET_RF_MODE,
+    SET_SENSOR_MODE,
+    SET_SWITCH_DELAY,
+    SET_TEMP,
+    SET_TEMP_FORMAT,
+    SET_TIME,
+    SET_TIMECLOCK,
+    SET_TIMER_FORMAT,
+    SET_TITLE,
+    STATISTICS,
+    STORE_C_PROFILE,
+    STORE_H_PROFILE,
+    STORE_PROFILE2,
+    STORE_PROFILE_0,
+    STORE_PROFILE_TIMER_0,
+    STORE_RECIPE,
+    SUMMER_OFF,
+    SUMMER_ON,
+    TIMER_HOLD_OFF,
+    TIMER_HOLD_ON,
+    TIMER_OFF,
+    TIMER_ON,
+    TIME_ZONE,
+    ULTRA_UPDATE_DONE,
+    UNLOCK,
+    USER_LIMIT,
+    VIEW_ROC,
+    WRITE_DCB,
+    ZONE_TITLE,
+];
+
+// commands which only read from the hub
+const READ_ONLY: &[&str] = &[
+    FIRMWARE,
+    GET_DATE,
+    GET_DEVICES,
+    GET_DEVICE_LIST,
+    GET_ENGINEERS,
+    GET_GROUPS,
+    GET_HOLD,
+    GET_HOLIDAY,
+    GET_HOURSRUN,
+    GET_LIVE_DATA,
+    GET_OEM_SETUP,
+    GET_PROFILES,
+    GET_PROFILE_0,
+    GET_PROFILE_TIMERS,
+    GET_RECIPES,
+    GET_SYSTEM,
+    GET_TEMPLOG,
+    GET_TIMER_0,
+    GET_TOKENS,
+    GET_ZONES,
+    GLOBAL_DEV_LIST,
+    GLOBAL_SYSTEM_TYPE,
+    OFFLINE_DEVICES,
+    READ_COMFORT_LEVELS,
+    READ_DCB,
+    READ_TIMECLOCK,
+    STATISTICS,
+    VIEW_ROC,
+    #[allow(deprecated)]
+    INFO,
+    #[allow(deprecated)]
+    ENGINEERS_DATA,
+    #[allow(deprecated)]
+    GET_PROFILE,
+    #[allow(deprecated)]
+    GET_PROFILE_NAMES,
+];
+
+// commands which can take the hub offline, or undo installation work; changing the zigbee
+// channel can orphan every device, and expiring a token can lock us out
+const DANGEROUS: &[&str] = &[
+    ALLOW_DOWNGRADE,
+    CLEAR_DEVICE_LIST,
+    DETACH_DEVICE,
+    DOWNLOAD_INSTALL,
+    EXPIRE_TOKEN,
+    REMOVE_REPEATER,
+    REMOVE_ZONE,
+    RESET,
+    RESET_HOMEKIT,
+    RESET_NCP,
+    SET_CHANNEL,
+];
+
+/// Whether a command is refused unless [`crate::Builder::allow_dangerous`] is set.
+pub fn is_dangerous(command: &str) -> bool {
+    DANGEROUS.contains(&command)
+}
+
+/// Whether a command only reads from the hub, i.e. can't change any state.
+pub fn is_read_only(command: &str) -> bool {
+    READ_ONLY.contains(&command)
+}
+
+/// A command, with typed arguments and response; send these with [`Client::send`].
+///
+/// These are low-level: e.g. zones must be given by name, and [`Builder::strict`] isn't
+/// applied. Prefer the methods on [`Client`] where they exist.
+///
+/// [`Client`]: crate::Client
+/// [`Client::send`]: crate::Client::send
+/// [`Builder::strict`]: crate::Builder::strict
+pub trait Command {
+    const NAME: &'static str;
+    type Output: DeserializeOwned;
+
+    fn args(&self) -> Value;
+}
+
+/// The response to commands which change state, e.g. `{"result": "temperature was set"}`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Ack {
+    pub result: String,
+}
+
+/// The response to `GET_SYSTEM`; only the settings this crate uses are typed.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct System {
+    /// `"C"` or `"F"`, see [`System::temperature_unit`].
+    pub corf: Option<String>,
+    pub format: Option<ScheduleFormat>,
+    /// Hours from UTC, e.g. `5.5`.
+    pub time_zone: Option<f64>,
+
+    // see `Device::extra`
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl System {
+    pub fn temperature_unit(&self) -> Option<TemperatureUnit> {
+        TemperatureUnit::from_corf(self.corf.as_deref()?)
+    }
+}
+
+// commands without arguments, which the hub wants as `0`
+macro_rules! void_commands {
+    ($($(#[$doc:meta])* $ty:ident = $name:ident -> $output:ty;)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct $ty;
+
+        impl Command for $ty {
+            const NAME: &'static str = $name;
+            type Output = $output;
+
+            fn args(&self) -> Value {
+                Value::from(0)
+            }
+        }
+    )*};
+}
+
+void_commands! {
+    Firmware = FIRMWARE -> Value;
+    GetDeviceList = GET_DEVICE_LIST -> Value;
+    GetEngineers = GET_ENGINEERS -> BTreeMap<String, EngineersData>;
+    GetHold = GET_HOLD -> Value;
+    GetHoliday = GET_HOLIDAY -> Value;
+    /// Parsed as-is; [`Client::live_data`](crate::Client::live_data) also handles older hubs.
+    GetLiveData = GET_LIVE_DATA -> LiveData;
+    /// Comfort levels, by zone.
+    GetProfile0 = GET_PROFILE_0 -> BTreeMap<String, Value>;
+    GetProfiles = GET_PROFILES -> BTreeMap<String, Profile>;
+    GetRecipes = GET_RECIPES -> BTreeMap<String, Value>;
+    GetSystem = GET_SYSTEM -> System;
+    GetZones = GET_ZONES -> Zones;
+    CancelHoldAll = CANCEL_HOLD_ALL -> Ack;
+    /// Set the hub's clock from the internet.
+    NtpOn = NTP_ON -> Ack;
+    NtpOff = NTP_OFF -> Ack;
+}
+
+// hub-wide settings, with a single argument
+macro_rules! hub_settings {
+    ($($(#[$doc:meta])* $ty:ident($value:ty) = $name:ident;)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone)]
+        pub struct $ty(pub $value);
+
+        impl Command for $ty {
+            const NAME: &'static str = $name;
+            type Output = Ack;
+
+            fn args(&self) -> Value {
+                json!(self.0)
+            }
+        }
+    )*};
+}
+
+hub_settings! {
+    /// How heating profiles apply to days.
+    SetFormat(ScheduleFormat) = SET_FORMAT;
+    /// How timeclock profiles apply to days.
+    SetTimerFormat(ScheduleFormat) = SET_TIMER_FORMAT;
+    /// Hours from UTC, in quarter hours.
+    SetTimeZone(f64) = TIME_ZONE;
+    /// Flash a thermostat's display, by zone name.
+    IdentifyDev(String) = IDENTIFY_DEV;
+    /// Delete a profile, by id.
+    ClearProfileId(u16) = CLEAR_PROFILE_ID;
+}
+
+macro_rules! zone_settings {
+    ($($(#[$doc:meta])* $ty:ident($value:ty) = $name:ident;)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone)]
+        pub struct $ty {
+            pub value: $value,
+            /// Zone names.
+            pub zones: Vec<String>,
+        }
+
+        impl Command for $ty {
+            const NAME: &'static str = $name;
+            type Output = Ack;
+
+            fn args(&self) -> Value {
+                json!([self.value, self.zones])
+            }
+        }
+    )*};
+}
+
+zone_settings! {
+    /// Four digits.
+    Lock([u8; 4]) = LOCK;
+    SetCoolTemp(f64) = SET_COOL_TEMP;
+    /// Degrees, 0-3.
+    SetDiff(u8) = SET_DIFF;
+    /// Minutes, 0-15.
+    SetDelay(u8) = SET_DELAY;
+    SetFanSpeed(FanSpeed) = SET_FAN_SPEED;
+    /// Degrees, 15-45.
+    SetFloor(u8) = SET_FLOOR;
+    SetHcMode(HcMode) = SET_HC_MODE;
+    /// Hours, 0-3.
+    SetPreheat(u8) = SET_PREHEAT;
+    SetTemp(f64) = SET_TEMP;
+    /// Minutes.
+    TimerHoldOn(u64) = TIMER_HOLD_ON;
+}
+
+macro_rules! zone_actions {
+    ($($(#[$doc:meta])* $ty:ident = $name:ident;)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone)]
+        pub struct $ty {
+            /// Zone names.
+            pub zones: Vec<String>,
+        }
+
+        impl Command for $ty {
+            const NAME: &'static str = $name;
+            type Output = Ack;
+
+            fn args(&self) -> Value {
+                json!(self.zones)
+            }
+        }
+    )*};
+}
+
+zone_actions! {
+    FrostOff = FROST_OFF;
+    FrostOn = FROST_ON;
+    Unlock = UNLOCK;
+}
+
+/// See [`Client::set_temperature_unit`](crate::Client::set_temperature_unit).
+#[derive(Debug, Clone, Copy)]
+pub struct SetTempFormat(pub TemperatureUnit);
+
+impl Command for SetTempFormat {
+    const NAME: &'static str = SET_TEMP_FORMAT;
+    type Output = Ack;
+
+    fn args(&self) -> Value {
+        json!(self.0.corf())
+    }
+}
+
+/// Cancel a boost, see [`Client::cancel_boost`](crate::Client::cancel_boost).
+#[derive(Debug, Clone)]
+pub struct TimerHoldOff {
+    /// Zone names.
+    pub zones: Vec<String>,
+}
+
+impl Command for TimerHoldOff {
+    const NAME: &'static str = TIMER_HOLD_OFF;
+    type Output = Ack;
+
+    fn args(&self) -> Value {
+        json!([0, self.zones])
+    }
+}
+
+/// Run a stored profile, see [`Client::run_profile`](crate::Client::run_profile).
+#[derive(Debug, Clone)]
+pub struct RunProfileId {
+    pub profile_id: u16,
+    /// Zone names.
+    pub zones: Vec<String>,
+}
+
+impl Command for RunProfileId {
+    const NAME: &'static str = RUN_PROFILE_ID;
+    type Output = Ack;
+
+    fn args(&self) -> Value {
+        let mut args = vec![Value::from(self.profile_id)];
+        args.extend(self.zones.iter().map(|zone| Value::from(zone.as_str())));
+        Value::from(args)
+    }
+}
+
+/// Hold zones at a temperature, see [`Client::hold`](crate::Client::hold).
+#[derive(Debug, Clone)]
+pub struct Hold {
+    pub temp: f64,
+    pub hours: u64,
+    pub minutes: u64,
+    /// A name for the hold.
+    pub id: String,
+    /// Zone names.
+    pub zones: Vec<String>,
+}
+
+impl Command for Hold {
+    const NAME: &'static str = HOLD;
+    type Output = Ack;
+
+    fn args(&self) -> Value {
+        json!([
+            { "temp": self.temp, "hours": self.hours, "minutes": self.minutes, "id": self.id },
+            self.zones,
+        ])
+    }
+}
+
+/// Store a profile, see [`Client::store_profile`](crate::Client::store_profile).
+#[derive(Debug, Clone)]
+pub struct StoreProfile2(pub Profile);
+
+impl Command for StoreProfile2 {
+    const NAME: &'static str = STORE_PROFILE2;
+    type Output = Ack;
+
+    fn args(&self) -> Value {
+        crate::profiles::store_args(&self.0)
+    }
+}
+
+/// Run a recipe, by name.
+#[derive(Debug, Clone)]
+pub struct RunRecipe(pub String);
+
+impl Command for RunRecipe {
+    const NAME: &'static str = RUN_RECIPE;
+    type Output = Ack;
+
+    fn args(&self) -> Value {
+        json!([self.0])
+    }
+}
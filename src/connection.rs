@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::events::OnEvent;
+use crate::protocol::{decode_frame, CommandResponse, Frame};
+use crate::{ConnectionEvent, InvalidToken, Transport};
+
+// a response, or an error the hub sent without saying which command it was for
+type Routed = std::result::Result<CommandResponse, String>;
+// commands waiting for a response, by COMMANDID
+type RouteMap = HashMap<i64, oneshot::Sender<Routed>>;
+type Routes = Arc<Mutex<RouteMap>>;
+
+/// A [`Transport`], with a task reading responses and handing them to whoever is waiting.
+///
+/// Commands can be sent from several tasks at once.
+pub(crate) struct Connection {
+    transport: Arc<dyn Transport>,
+    // a write was cancelled part-way through, so the connection is unusable
+    poisoned: AtomicBool,
+    routes: Routes,
+    closed: Closed,
+    reader: JoinHandle<()>,
+}
+
+// reports `Disconnected` once, whether the reader or the client notices first
+#[derive(Clone)]
+struct Closed {
+    reported: Arc<AtomicBool>,
+    on_event: Option<Arc<OnEvent>>,
+}
+
+impl Closed {
+    fn report(&self, reason: String) {
+        if self.reported.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        if let Some(on_event) = &self.on_event {
+            on_event(ConnectionEvent::Disconnected { reason });
+        }
+    }
+}
+
+impl Connection {
+    pub(crate) fn new(transport: Arc<dyn Transport>, on_event: Option<Arc<OnEvent>>) -> Self {
+        let routes = Routes::default();
+        let closed = Closed {
+            reported: Arc::default(),
+            on_event,
+        };
+        let reader = tokio::spawn(read(transport.clone(), routes.clone(), closed.clone()));
+        Connection {
+            transport,
+            poisoned: AtomicBool::new(false),
+            routes,
+            closed,
+            reader,
+        }
+    }
+
+    /// We're giving up on this connection; report it, unless the hub closed it first.
+    pub(crate) fn report_closed(&self, reason: impl Into<String>) {
+        self.closed.report(reason.into());
+    }
+
+    /// The hub has gone away, or sent something unreadable.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.reader.is_finished()
+    }
+
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::SeqCst)
+    }
+
+    // register before sending, so the response can't arrive before we're listening
+    pub(crate) fn expect(&self, command_id: i64) -> Pending {
+        let (tx, rx) = oneshot::channel();
+        lock(&self.routes).insert(command_id, tx);
+        Pending {
+            command_id,
+            rx,
+            routes: self.routes.clone(),
+        }
+    }
+
+    pub(crate) async fn send(&self, frame: String) -> Result<()> {
+        // unless we finish, e.g. if we're cancelled, the connection is unusable
+        let poison = Poison(&self.poisoned);
+        self.transport.send(frame).await?;
+        std::mem::forget(poison);
+        Ok(())
+    }
+
+    /// Finishes when the hub answers, see [`Transport::ping`].
+    pub(crate) async fn ping(&self) -> Result<()> {
+        self.transport.ping().await
+    }
+
+    pub(crate) async fn close(&self) -> Result<()> {
+        self.transport.close().await
+    }
+}
+
+struct Poison<'a>(&'a AtomicBool);
+
+impl Drop for Poison<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// A response we're waiting for; dropping this forgets about it.
+pub(crate) struct Pending {
+    command_id: i64,
+    rx: oneshot::Receiver<Routed>,
+    routes: Routes,
+}
+
+impl Pending {
+    pub(crate) async fn recv(mut self) -> Result<CommandResponse> {
+        let routed = (&mut self.rx)
+            .await
+            .map_err(|_| anyhow!("connection closed before a response was received"))?;
+        routed.map_err(|message| {
+            if message.to_ascii_lowercase().contains("token") {
+                anyhow::Error::new(InvalidToken(message))
+            } else {
+                anyhow!("hub error: {}", message)
+            }
+        })
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        lock(&self.routes).remove(&self.command_id);
+    }
+}
+
+async fn read(transport: Arc<dyn Transport>, routes: Routes, closed: Closed) {
+    let reason = loop {
+        let buf = match transport.recv().await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break "closed by the hub".to_string(),
+            Err(e) => {
+                warn!("closing connection: {:#}", e);
+                break format!("{:#}", e);
+            }
+        };
+        let resp = match decode_frame(buf.as_bytes()) {
+            Frame::Response(resp) => resp,
+            Frame::Error(message) => {
+                warn!("hub error: {}", message);
+                // we don't know who it's for, so tell everyone
+                for (_, tx) in lock(&routes).drain() {
+                    drop(tx.send(Err(message.clone())));
+                }
+                continue;
+            }
+            Frame::Unsolicited(resp) => {
+                debug!("ignoring unsolicited message: {:?}", resp);
+                continue;
+            }
+            Frame::Unreadable(e) => {
+                warn!("ignoring unreadable message ({}): {:?}", e, buf);
+                continue;
+            }
+        };
+        match lock(&routes).remove(&resp.command_id) {
+            // the receiver may have just given up; that's fine
+            Some(tx) => drop(tx.send(Ok(resp))),
+            None => debug!(
+                "discarding response to cancelled command {}",
+                resp.command_id
+            ),
+        }
+    };
+    debug!("connection closed: {}", reason);
+    // before waking anyone, so this is reported before any reconnection
+    closed.report(reason);
+    // wakes everyone who's waiting, with an error
+    lock(&routes).clear();
+}
+
+fn lock(routes: &Routes) -> MutexGuard<'_, RouteMap> {
+    // nothing can panic while holding the lock
+    routes.lock().expect("routes poisoned")
+}
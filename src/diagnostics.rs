@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{commands, Client, Zone};
+
+/// What the hub records about itself, for attaching to bug reports; see
+/// [`Client::diagnostics`].
+///
+/// The formats aren't documented, and vary by firmware, so these are left as json.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Diagnostics {
+    pub firmware_version: Option<String>,
+    // GET_SYSTEM
+    pub system: Value,
+    // STATISTICS
+    pub statistics: Option<Value>,
+    // GET_HOURSRUN, by zone
+    pub hours_run: Option<Value>,
+    // GET_TEMPLOG, by zone
+    pub temperature_log: Option<Value>,
+    // OFFLINE_DEVICES
+    pub offline_devices: Option<Value>,
+    /// Why any of the above are missing, by command; older firmware lacks some.
+    pub errors: BTreeMap<String, String>,
+}
+
+impl Client {
+    /// The hub's counters, from `STATISTICS`.
+    pub async fn statistics(&self) -> Result<Value> {
+        self.command_value(commands::STATISTICS, Value::Null).await
+    }
+
+    /// How long each zone has been calling for heat, from `GET_HOURSRUN`.
+    pub async fn hours_run(&self) -> Result<Value> {
+        self.command_value(commands::GET_HOURSRUN, Value::Null)
+            .await
+    }
+
+    /// Recent temperatures the hub has logged for `zones`, from `GET_TEMPLOG`.
+    pub async fn temperature_log(
+        &self,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<Value> {
+        let zones = self.resolve_zones(zones).await?;
+        self.command_value(commands::GET_TEMPLOG, json!(zones))
+            .await
+    }
+
+    /// Everything the hub will tell us about itself, for support investigations.
+    ///
+    /// Only failing to reach the hub is an error; commands this firmware doesn't support
+    /// are listed in [`Diagnostics::errors`].
+    pub async fn diagnostics(&self) -> Result<Diagnostics> {
+        let firmware_version = self.identify().await?.firmware_version;
+        let system = self
+            .command_value(commands::GET_SYSTEM, Value::Null)
+            .await
+            .context("reading system settings")?;
+        let mut errors = BTreeMap::new();
+        let statistics = optional(&mut errors, commands::STATISTICS, self.statistics().await);
+        let hours_run = optional(&mut errors, commands::GET_HOURSRUN, self.hours_run().await);
+        let temperature_log = async {
+            let zones = self.all_zone_names().await.context("listing zones")?;
+            self.temperature_log(zones).await
+        };
+        let temperature_log = optional(&mut errors, commands::GET_TEMPLOG, temperature_log.await);
+        let offline_devices = optional(
+            &mut errors,
+            commands::OFFLINE_DEVICES,
+            self.command_value(commands::OFFLINE_DEVICES, Value::Null)
+                .await,
+        );
+        Ok(Diagnostics {
+            firmware_version,
+            system,
+            statistics,
+            hours_run,
+            temperature_log,
+            offline_devices,
+            errors,
+        })
+    }
+}
+
+fn optional(
+    errors: &mut BTreeMap<String, String>,
+    command: &str,
+    result: Result<Value>,
+) -> Option<Value> {
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("diagnostics: {} failed: {:#}", command, e);
+            errors.insert(command.to_string(), format!("{:#}", e));
+            None
+        }
+    }
+}
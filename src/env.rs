@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use crate::{builder, Builder};
+
+// see `Client::from_env`
+pub(crate) fn builder_from_env() -> Result<Builder> {
+    let file = dotenv()?;
+    let var = |key: &str| {
+        std::env::var(key)
+            .ok()
+            .or_else(|| file.get(key).cloned())
+            .filter(|value| !value.is_empty())
+    };
+
+    let url = match (var("NEOHUB_URL"), var("NEOHUB_HOST")) {
+        (Some(url), None) => {
+            ensure!(
+                var("NEOHUB_PORT").is_none(),
+                "NEOHUB_PORT only applies to NEOHUB_HOST; put the port in NEOHUB_URL"
+            );
+            url
+        }
+        (None, Some(host)) => {
+            let port = var("NEOHUB_PORT")
+                .map(|port| port.parse())
+                .transpose()
+                .with_context(|| "parsing NEOHUB_PORT")?;
+            builder::host_url(&host, port).with_context(|| "from NEOHUB_HOST")?
+        }
+        (Some(_), Some(_)) => bail!("set either NEOHUB_URL or NEOHUB_HOST, not both"),
+        (None, None) => bail!("env var required: \"NEOHUB_URL\" (or \"NEOHUB_HOST\")"),
+    };
+    let token = var("NEOHUB_TOKEN").ok_or_else(|| anyhow!("env var required: \"NEOHUB_TOKEN\""))?;
+
+    let mut builder = Builder::new(url, token);
+    if let Some(secs) = var("NEOHUB_TIMEOUT_SECS") {
+        let secs: f64 = secs
+            .parse()
+            .with_context(|| "parsing NEOHUB_TIMEOUT_SECS")?;
+        let timeout = Duration::try_from_secs_f64(secs)
+            .with_context(|| anyhow!("NEOHUB_TIMEOUT_SECS out of range: {}", secs))?;
+        builder = builder.timeout(timeout);
+    }
+    Ok(builder)
+}
+
+#[cfg(feature = "dotenv")]
+fn dotenv() -> Result<HashMap<String, String>> {
+    match std::fs::read_to_string(".env") {
+        Ok(text) => parse_dotenv(&text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e).with_context(|| "reading .env"),
+    }
+}
+
+#[cfg(not(feature = "dotenv"))]
+fn dotenv() -> Result<HashMap<String, String>> {
+    Ok(HashMap::new())
+}
+
+// KEY=value lines, allowing `export`, comments, and quoted values; no interpolation
+#[cfg(feature = "dotenv")]
+fn parse_dotenv(text: &str) -> Result<HashMap<String, String>> {
+    let mut vars = HashMap::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!(".env line {}: expected KEY=value", n + 1))?;
+        let value = value.trim();
+        let value = [('"', '"'), ('\'', '\'')]
+            .iter()
+            .find_map(|&(open, close)| value.strip_prefix(open)?.strip_suffix(close))
+            .unwrap_or(value);
+        vars.insert(key.trim().to_string(), value.to_string());
+    }
+    Ok(vars)
+}
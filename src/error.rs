@@ -0,0 +1,46 @@
+use std::fmt;
+
+/// The hub didn't accept our API token; find these with `anyhow::Error::downcast_ref`.
+#[derive(Debug, Clone)]
+pub struct InvalidToken(
+    // what the hub said
+    pub String,
+);
+
+impl fmt::Display for InvalidToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the hub rejected the API token: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidToken {}
+
+/// The hub refused a command, e.g. for an unknown zone or a bad argument.
+#[derive(Debug, Clone)]
+pub struct HubRejected {
+    pub command: String,
+    // what the hub said
+    pub reason: String,
+}
+
+impl fmt::Display for HubRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the hub rejected {}: {}", self.command, self.reason)
+    }
+}
+
+impl std::error::Error for HubRejected {}
+
+// the hub reports errors like {"error": "Invalid argument"}
+pub(crate) fn rejection(command: &str, resp: &str) -> Option<HubRejected> {
+    // cheap check first, as responses can be large
+    if !resp.contains("\"error\"") {
+        return None;
+    }
+    let resp: serde_json::Value = serde_json::from_str(resp).ok()?;
+    let reason = resp.as_object()?.get("error")?.as_str()?;
+    Some(HubRejected {
+        command: command.to_string(),
+        reason: reason.to_string(),
+    })
+}
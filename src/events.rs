@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+pub(crate) type OnEvent = dyn Fn(ConnectionEvent) + Send + Sync;
+
+/// A change in the client's connection to the hub, see [`crate::Builder::on_event`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    Connected,
+    /// The connection was lost, or closed with [`crate::Client::disconnect`]; the next
+    /// command reconnects.
+    Disconnected {
+        reason: String,
+    },
+    /// About to try connecting again, after losing the connection or failing to connect;
+    /// `attempt` counts from 1 for each reconnection.
+    Reconnecting {
+        attempt: u32,
+    },
+}
@@ -0,0 +1,174 @@
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{commands, Client, LiveData};
+
+/// Which generation of hub we're talking to; they return differently shaped responses.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Generation {
+    /// Original neoHubs, which omit many fields newer firmware reports.
+    First,
+    /// neoHub v2 / neoHub Mini, with firmware 2000 and later.
+    Second,
+}
+
+impl Generation {
+    /// Classify a firmware version from `FIRMWARE`, e.g. "2134".
+    pub fn from_firmware(version: &str) -> Option<Generation> {
+        let digits: String = version
+            .trim()
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        let version: u32 = digits.parse().ok()?;
+        Some(if version >= 2000 {
+            Generation::Second
+        } else {
+            Generation::First
+        })
+    }
+}
+
+impl Client {
+    /// The hub's generation, detected from its firmware version on first use, unless
+    /// set with [`crate::Builder::generation`].
+    pub async fn generation(&self) -> Result<Generation> {
+        if let Some(generation) = *self.generation.lock().expect("client poisoned") {
+            return Ok(generation);
+        }
+        let generation = self.detect_generation().await?;
+        debug!("detected hub generation: {:?}", generation);
+        *self.generation.lock().expect("client poisoned") = Some(generation);
+        Ok(generation)
+    }
+
+    async fn detect_generation(&self) -> Result<Generation> {
+        let firmware: Value = self.command_void(commands::FIRMWARE).await?;
+        if let Some(generation) = version_of(&firmware, "firmware version") {
+            return Ok(generation);
+        }
+        let system: Value = self
+            .command_void(commands::GET_SYSTEM)
+            .await
+            .context("FIRMWARE had no version, trying GET_SYSTEM")?;
+        version_of(&system, "HUB_VERSION")
+            .ok_or_else(|| anyhow!("couldn't find a firmware version: {firmware} / {system}"))
+    }
+
+    /// The hub's current state, from `GET_LIVE_DATA`.
+    pub async fn live_data(&self) -> Result<LiveData> {
+        let generation = self.generation().await?;
+        // only older hubs' responses need patching up as a `Value`; the rest are parsed
+        // straight from the response, which is faster for large installs
+        let live_data: LiveData = if generation == Generation::First {
+            let mut value: Value = self.command_void(commands::GET_LIVE_DATA).await?;
+            fill_legacy_live_data(&mut value);
+            match LiveData::deserialize(&value) {
+                Ok(live_data) => live_data,
+                Err(e) => {
+                    self.keep_unparsed(value.to_string());
+                    return Err(
+                        anyhow!(e).context("reading GET_LIVE_DATA, see Client::last_raw_response")
+                    );
+                }
+            }
+        } else {
+            self.command_void(commands::GET_LIVE_DATA)
+                .await
+                .context("reading GET_LIVE_DATA, see Client::last_raw_response")?
+        };
+        self.check_strict(commands::GET_LIVE_DATA, &live_data)?;
+        Ok(live_data)
+    }
+}
+
+// versions are sometimes strings, sometimes numbers
+fn version_of(value: &Value, key: &str) -> Option<Generation> {
+    match value.get(key)? {
+        Value::String(version) => Generation::from_firmware(version),
+        Value::Number(version) => Generation::from_firmware(&version.to_string()),
+        _ => None,
+    }
+}
+
+// First generation hubs don't report many fields, so default them to "nothing to see".
+fn fill_legacy_live_data(live_data: &mut Value) {
+    let header = json!({
+        "HUB_AWAY": false,
+        "HUB_HOLIDAY": false,
+        "HOLIDAY_END": 0,
+        "TIMESTAMP_DEVICE_LISTS": 0,
+        "TIMESTAMP_ENGINEERS": 0,
+        "TIMESTAMP_PROFILE_0": 0,
+        "TIMESTAMP_PROFILE_COMFORT_LEVELS": 0,
+        "TIMESTAMP_PROFILE_TIMERS": 0,
+        "TIMESTAMP_PROFILE_TIMERS_0": 0,
+        "TIMESTAMP_RECIPES": 0,
+        "COOL_INPUT": false,
+        "CLOSE_DELAY": 0,
+        "OPEN_DELAY": 0,
+    });
+    let device = json!({
+        "ACTIVE_LEVEL": 0,
+        "ACTIVE_PROFILE": 0,
+        "AVAILABLE_MODES": ["heat"],
+        "AWAY": false,
+        "HOLIDAY": false,
+        "COOL_MODE": false,
+        "COOL_ON": false,
+        "COOL_TEMP": 0,
+        "CURRENT_FLOOR_TEMPERATURE": 127.5,
+        "PRG_TEMP": 0,
+        "RECENT_TEMPS": [],
+        "RELATIVE_HUMIDITY": 0,
+        "DATE": "",
+        "TIME": "",
+        "FAN_CONTROL": "",
+        "FAN_SPEED": "",
+        "FLOOR_LIMIT": false,
+        "HC_MODE": "HEATING",
+        "HEAT_MODE": true,
+        "HEAT_ON": false,
+        "HOLD_COOL": 0,
+        "HOLD_OFF": false,
+        "HOLD_ON": false,
+        "HOLD_TEMP": 0,
+        "HOLD_TIME": "0:00",
+        "LOCK": false,
+        "LOW_BATTERY": false,
+        "MANUAL_OFF": false,
+        "MODELOCK": false,
+        "MODULATION_LEVEL": 0,
+        "OFFLINE": false,
+        "PIN_NUMBER": "0000",
+        "PREHEAT_ACTIVE": false,
+        "PRG_TIMER": false,
+        "STANDBY": false,
+        "SWITCH_DELAY_LEFT": "0:00",
+        "TEMPORARY_SET_FLAG": false,
+        "TIMER_ON": false,
+        "WINDOW_OPEN": false,
+        "WRITE_COUNT": 0,
+    });
+
+    fill_missing(live_data, &header);
+    if let Some(devices) = live_data.get_mut("devices").and_then(Value::as_array_mut) {
+        for entry in devices {
+            // only thermostats and timeclocks; leave sensors etc. alone
+            if entry.get("ACTUAL_TEMP").is_some() {
+                fill_missing(entry, &device);
+            }
+        }
+    }
+}
+
+fn fill_missing(target: &mut Value, defaults: &Value) {
+    let (Some(target), Some(defaults)) = (target.as_object_mut(), defaults.as_object()) else {
+        return;
+    };
+    for (key, value) in defaults {
+        target.entry(key).or_insert_with(|| value.clone());
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use crate::Client;
+
+/// A named set of zones, e.g. "Upstairs"; the hub has no groups of its own.
+///
+/// Operations are sent for each zone separately, all at once over one [`Client`], so one
+/// failing zone doesn't stop the rest.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ZoneGroup {
+    pub name: String,
+    /// Zone names.
+    pub zones: Vec<String>,
+}
+
+/// How each zone in a [`ZoneGroup`] operation went, by zone name.
+#[derive(Debug, Default)]
+pub struct GroupReport {
+    pub results: BTreeMap<String, Result<()>>,
+}
+
+impl GroupReport {
+    pub fn is_ok(&self) -> bool {
+        self.results.values().all(Result::is_ok)
+    }
+
+    pub fn failed(&self) -> Vec<(&str, &anyhow::Error)> {
+        self.results
+            .iter()
+            .filter_map(|(zone, result)| Some((zone.as_str(), result.as_ref().err()?)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    SetTemperature(f64),
+    Hold(f64, Duration),
+    Standby,
+    Resume,
+}
+
+impl ZoneGroup {
+    pub fn new(name: impl ToString, zones: impl IntoIterator<Item = impl ToString>) -> Self {
+        ZoneGroup {
+            name: name.to_string(),
+            zones: zones.into_iter().map(|zone| zone.to_string()).collect(),
+        }
+    }
+
+    /// See [`Client::set_temperature`].
+    pub async fn set_temperature(&self, client: &Client, temp: f64) -> GroupReport {
+        self.run(client, Op::SetTemperature(temp)).await
+    }
+
+    /// See [`Client::hold`].
+    pub async fn hold(&self, client: &Client, temp: f64, duration: Duration) -> GroupReport {
+        self.run(client, Op::Hold(temp, duration)).await
+    }
+
+    /// See [`Client::standby`].
+    pub async fn standby(&self, client: &Client) -> GroupReport {
+        self.run(client, Op::Standby).await
+    }
+
+    /// See [`Client::resume`].
+    pub async fn resume(&self, client: &Client) -> GroupReport {
+        self.run(client, Op::Resume).await
+    }
+
+    async fn run(&self, client: &Client, op: Op) -> GroupReport {
+        let results = join_all(
+            self.zones
+                .iter()
+                .map(|zone| async move { (zone.clone(), run_one(client, op, zone).await) }),
+        )
+        .await;
+        GroupReport {
+            results: results.into_iter().collect(),
+        }
+    }
+}
+
+async fn run_one(client: &Client, op: Op, zone: &str) -> Result<()> {
+    match op {
+        Op::SetTemperature(temp) => client.set_temperature(temp, [zone]).await,
+        Op::Hold(temp, duration) => client.hold(temp, duration, [zone]).await,
+        Op::Standby => client.standby([zone]).await,
+        Op::Resume => client.resume([zone]).await,
+    }
+}
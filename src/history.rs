@@ -0,0 +1,231 @@
+//! Append-only local storage for polled [`LiveData`], one JSON line per snapshot.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{DeviceRef, LiveData};
+
+/// One zone's readings at one time.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// Unix time, by the hub's clock.
+    pub time: i64,
+    pub temperature: Option<f64>,
+    pub set_temperature: Option<f64>,
+    pub heat_on: bool,
+    pub humidity: Option<u8>,
+    /// For contact sensors, and thermostats which report it.
+    pub window_open: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Line {
+    time: i64,
+    zones: BTreeMap<String, Reading>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Reading {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    set_temperature: Option<f64>,
+    #[serde(default)]
+    heat_on: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    humidity: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    window_open: Option<bool>,
+}
+
+impl Reading {
+    fn of(device: DeviceRef) -> Option<Reading> {
+        match device {
+            DeviceRef::Device(device) => Some(Reading {
+                temperature: device.actual_temp.trim().parse().ok(),
+                set_temperature: device.set_temp.trim().parse().ok(),
+                heat_on: device.heat_on,
+                humidity: device.humidity(),
+                window_open: Some(device.window_open),
+            }),
+            DeviceRef::ContactSensor(sensor) => Some(Reading {
+                temperature: None,
+                set_temperature: None,
+                heat_on: false,
+                humidity: None,
+                window_open: Some(sensor.window_open),
+            }),
+            DeviceRef::Repeater(_) => None,
+        }
+    }
+}
+
+/// A history file; queries read it incrementally, so it's only read once.
+pub struct History {
+    path: PathBuf,
+    file: File,
+    // everything read so far, by zone
+    samples: BTreeMap<String, Vec<Sample>>,
+    read_to: u64,
+    lines_read: usize,
+    corrupt_lines: usize,
+}
+
+impl History {
+    /// Open (or create) a history file, for appending.
+    pub fn open(path: impl AsRef<Path>) -> Result<History> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| anyhow!("opening history file {:?}", path))?;
+        // a line torn by e.g. a power cut; don't append to it
+        if file.metadata()?.len() > 0 {
+            let mut last = [0];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last != *b"\n" {
+                file.write_all(b"\n")
+                    .with_context(|| anyhow!("appending to {:?}", path))?;
+            }
+        }
+        Ok(History {
+            path,
+            file,
+            samples: BTreeMap::new(),
+            read_to: 0,
+            lines_read: 0,
+            corrupt_lines: 0,
+        })
+    }
+
+    /// Record the devices' and contact sensors' readings.
+    pub fn record(&mut self, live_data: &LiveData) -> Result<()> {
+        let line = Line {
+            time: live_data.hub_time(),
+            zones: live_data
+                .all_devices()
+                .filter_map(|device| Some((device.zone_name().to_string(), Reading::of(device)?)))
+                .collect(),
+        };
+        let mut json = serde_json::to_string(&line)?;
+        json.push('\n');
+        self.file
+            .write_all(json.as_bytes())
+            .with_context(|| anyhow!("appending to {:?}", self.path))
+    }
+
+    /// Samples for `zone` with times in `range`, oldest first.
+    ///
+    /// Lines which can't be read are logged and skipped, see [`History::corrupt_lines`].
+    pub fn query(&mut self, zone: &str, range: Range<i64>) -> Result<Vec<Sample>> {
+        self.catch_up()?;
+        let mut samples: Vec<Sample> = self
+            .samples
+            .get(zone)
+            .into_iter()
+            .flatten()
+            .filter(|sample| range.contains(&sample.time))
+            .cloned()
+            .collect();
+        samples.sort_by_key(|sample| sample.time);
+        Ok(samples)
+    }
+
+    /// Lines skipped so far, as they couldn't be read.
+    pub fn corrupt_lines(&self) -> usize {
+        self.corrupt_lines
+    }
+
+    // read anything appended since last time
+    fn catch_up(&mut self) -> Result<()> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.read_to))?;
+        let mut reader = BufReader::new(file);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let read = reader
+                .read_until(b'\n', &mut buf)
+                .with_context(|| anyhow!("reading {:?}", self.path))?;
+            // an unfinished line may still be being written; read it next time
+            if read == 0 || !buf.ends_with(b"\n") {
+                return Ok(());
+            }
+            self.read_to += read as u64;
+            self.lines_read += 1;
+            if buf.trim_ascii().is_empty() {
+                continue;
+            }
+            let line: Line = match serde_json::from_slice(&buf) {
+                Ok(line) => line,
+                Err(e) => {
+                    warn!("skipping {:?} line {}: {}", self.path, self.lines_read, e);
+                    self.corrupt_lines += 1;
+                    continue;
+                }
+            };
+            for (zone, reading) in line.zones {
+                self.samples.entry(zone).or_default().push(Sample {
+                    time: line.time,
+                    temperature: reading.temperature,
+                    set_temperature: reading.set_temperature,
+                    heat_on: reading.heat_on,
+                    humidity: reading.humidity,
+                    window_open: reading.window_open,
+                });
+            }
+        }
+    }
+}
+
+/// Average `samples` (sorted by time) into buckets of `bucket`, each timed at its start.
+///
+/// `heat_on` (and `window_open`) is true if the heat was on for any sample in the bucket.
+pub fn downsample(samples: &[Sample], bucket: Duration) -> Vec<Sample> {
+    let width = (bucket.as_secs() as i64).max(1);
+    let mut out: Vec<Sample> = Vec::new();
+    let mut group: Vec<&Sample> = Vec::new();
+    let flush = |group: &mut Vec<&Sample>, out: &mut Vec<Sample>| {
+        if let Some(first) = group.first() {
+            out.push(Sample {
+                time: first.time.div_euclid(width) * width,
+                temperature: mean(group.iter().filter_map(|s| s.temperature)),
+                set_temperature: mean(group.iter().filter_map(|s| s.set_temperature)),
+                heat_on: group.iter().any(|s| s.heat_on),
+                humidity: mean(group.iter().filter_map(|s| s.humidity.map(f64::from)))
+                    .map(|h| h.round() as u8),
+                window_open: group
+                    .iter()
+                    .filter_map(|s| s.window_open)
+                    .reduce(|a, b| a || b),
+            });
+        }
+        group.clear();
+    };
+    for sample in samples {
+        if let Some(first) = group.first() {
+            if first.time.div_euclid(width) != sample.time.div_euclid(width) {
+                flush(&mut group, &mut out);
+            }
+        }
+        group.push(sample);
+    }
+    flush(&mut group, &mut out);
+    out
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
@@ -1,55 +1,223 @@
+pub mod alerts;
+mod audit;
+mod backup;
+mod builder;
+mod cache;
+mod cached_client;
+mod clock;
 pub mod commands;
+mod connection;
+mod diagnostics;
+mod env;
+mod error;
+mod events;
+mod firmware;
+mod groups;
+#[cfg(feature = "history")]
+pub mod history;
 mod live_data;
-
-use std::sync::Arc;
-use std::time::Duration;
+mod maintenance;
+mod middleware;
+mod network;
+mod poll;
+mod pool;
+mod profile_diff;
+mod profile_file;
+mod profiles;
+pub mod protocol;
+mod proxy;
+mod recipes;
+mod schedule;
+mod scheduler;
+mod server;
+mod settings;
+pub mod stats;
+mod strict;
+mod sync;
+mod thermostat;
+mod transport;
+mod units;
+mod validate;
+mod zones;
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, ensure, Context, Result};
-use futures_util::{SinkExt, StreamExt};
-use log::debug;
+use log::{debug, info, warn};
 use rustls::client::danger;
 use rustls::crypto::ring::default_provider;
 use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, WebPkiSupportedAlgorithms};
 use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
 use rustls::{DigitallySignedStruct, Error, SignatureScheme};
 use serde::de::DeserializeOwned;
-use serde::Deserialize;
-use serde::Serialize;
-use serde_json::{json, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::net::TcpStream;
 use tokio::time::timeout;
-use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::{
-    connect_async_tls_with_config, Connector, MaybeTlsStream, WebSocketStream,
+use tokio_tungstenite::{client_async_tls_with_config, Connector, MaybeTlsStream, WebSocketStream};
+use url::{Host, Url};
+
+use crate::audit::OnCommand;
+use crate::connection::Connection;
+use crate::events::OnEvent;
+use crate::protocol::{
+    command_name, decode_response, encode, encode_void, single_command, Session,
 };
-
-pub use live_data::LiveData;
+use crate::transport::{Connect, WebSocket};
+pub use audit::{CommandRecord, Outcome};
+pub use backup::{Backup, Change, RestoreReport};
+pub use builder::Builder;
+pub use cache::Cache;
+pub use cached_client::CachedClient;
+pub use clock::{HubDateTime, TimeZone};
+pub use diagnostics::Diagnostics;
+pub use error::{HubRejected, InvalidToken};
+pub use events::ConnectionEvent;
+pub use firmware::Generation;
+pub use groups::{GroupReport, ZoneGroup};
+pub use live_data::{
+    ContactSensor, Device, DeviceFault, DeviceRef, DeviceType, LiveData, OverrideState,
+    PowerSource, Repeater, Timestamps, UnparsedDevice,
+};
+pub use maintenance::DangerZone;
+pub use middleware::{Middleware, Next};
+pub use network::{Link, NetworkHealth};
+pub use poll::{Poller, Snapshot};
+pub use pool::{Pool, Pooled};
+pub use profile_diff::{diff_profiles, ProfileChange, ProfileDiff};
+pub use profiles::{ComfortLevel, Day, Profile, ProfileInfo, ProfileInfoDay, ProfileRef};
+pub use recipes::Recipe;
+pub use schedule::{DayTimeline, Event, ScheduleFormat, Segment, Setpoint, Setpoints};
+pub use scheduler::{Action, Job, Recurrence, Scheduler};
+pub use server::Server;
+pub use strict::UnknownFields;
+pub use sync::{sync_profiles, SyncReport};
+pub use thermostat::{EngineersData, FanSpeed, HcMode, Hold};
+pub use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+pub use transport::Transport;
+pub use units::TemperatureUnit;
+pub use validate::{InvalidProfile, ProfileProblem};
+pub use zones::{Zone, Zones};
 
 type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
+/// A connection to a hub; commands take `&self`, so this can be shared (e.g. in an
+/// `Arc`) and used from several tasks at once.
 pub struct Client {
     url: String,
-    token: String,
-    conn: Option<WsStream>,
+    // the token, and command ids
+    session: Session,
+    // replaced when it fails; see `Client::ensure_connected`
+    conn: Mutex<Option<Arc<Connection>>>,
+    // held while connecting, so only one task does
+    connecting: tokio::sync::Mutex<()>,
+    // so later connections are reported as reconnecting
+    connected_before: AtomicBool,
     opts: Opts,
+    // see `Client::generation`
+    generation: Mutex<Option<Generation>>,
+    // see `Client::temperature_unit`
+    temperature_unit: Mutex<Option<TemperatureUnit>>,
+    // when we last heard from the hub, see `Builder::liveness_check`
+    last_used: Mutex<Option<Instant>>,
+    // the latest responses, oldest first; see `Builder::retain_frames`
+    frames: Mutex<VecDeque<String>>,
+    // see `Client::last_raw_response`
+    unparsed: Mutex<Option<String>>,
 }
 
 #[non_exhaustive]
+#[derive(Clone)]
 pub struct Opts {
     pub timeout: Duration,
+    // see `Builder::dry_run`
+    pub dry_run: bool,
+    // see `Builder::read_only`
+    pub read_only: bool,
+    // see `Builder::allow_dangerous`
+    pub allow_dangerous: bool,
+    // see `Builder::on_command`
+    pub on_command: Option<Arc<OnCommand>>,
+    // see `Builder::on_event`
+    pub on_event: Option<Arc<OnEvent>>,
+    // see `Builder::generation`
+    pub generation: Option<Generation>,
+    // see `Builder::temperature_unit`
+    pub temperature_unit: Option<TemperatureUnit>,
+    // see `Builder::strict`
+    pub strict: bool,
+    // see `Builder::middleware`, outermost first
+    pub middleware: Vec<Arc<dyn Middleware>>,
+    // see `Builder::websocket_config`
+    pub websocket_config: Option<WebSocketConfig>,
+    // see `Builder::proxy`
+    pub proxy: Option<String>,
+    // see `Builder::address`
+    pub address: Option<SocketAddr>,
+    // see `Builder::connect_retry`; `None` for the default, which depends on how it's built
+    pub connect_attempts: Option<u32>,
+    pub connect_backoff: Duration,
+    // see `Builder::liveness_check`
+    pub liveness_idle: Option<Duration>,
+    pub liveness_deadline: Duration,
+    // see `Builder::retain_frames`
+    pub retain_frames: usize,
+    // see `Builder::transport`
+    pub transport: Option<Arc<Connect>>,
 }
 
 impl Default for Opts {
     fn default() -> Self {
         Self {
             timeout: Duration::from_secs(15),
+            dry_run: false,
+            read_only: false,
+            allow_dangerous: false,
+            on_command: None,
+            on_event: None,
+            generation: None,
+            temperature_unit: None,
+            strict: false,
+            middleware: Vec::new(),
+            websocket_config: None,
+            proxy: None,
+            address: None,
+            connect_attempts: None,
+            connect_backoff: Duration::from_secs(1),
+            liveness_idle: None,
+            liveness_deadline: Duration::from_secs(2),
+            retain_frames: 0,
+            transport: None,
         }
     }
 }
 
 impl Client {
+    /// Configure a client from `NEOHUB_URL` (or `NEOHUB_HOST` and optionally `NEOHUB_PORT`),
+    /// `NEOHUB_TOKEN`, and optionally `NEOHUB_TIMEOUT_SECS`.
+    ///
+    /// With the `dotenv` feature, these can also come from a `.env` file in the current
+    /// directory; the environment wins.
     pub fn from_env() -> Result<Self> {
-        Self::new(env_var("NEOHUB_URL")?, env_var("NEOHUB_TOKEN")?)
+        Builder::from_env()?.build()
+    }
+
+    pub fn builder(url: impl ToString, token: impl ToString) -> Builder {
+        Builder::new(url, token)
+    }
+
+    /// Configure a client for the hub at `host` (a name or an ip address), on `port`, or
+    /// the hub's usual port (4243).
+    pub fn build_host(
+        host: &str,
+        port: impl Into<Option<u16>>,
+        token: impl ToString,
+    ) -> Result<Builder> {
+        Ok(Builder::new(builder::host_url(host, port.into())?, token))
     }
 
     pub fn new(url: impl ToString, token: impl ToString) -> Result<Self> {
@@ -59,167 +227,462 @@ impl Client {
     pub fn new_opts(url: impl ToString, token: impl ToString, opts: Opts) -> Result<Self> {
         Ok(Client {
             url: url.to_string(),
-            token: token.to_string(),
-            conn: None,
+            session: Session::new(token),
+            conn: Mutex::new(None),
+            connecting: tokio::sync::Mutex::new(()),
+            connected_before: AtomicBool::new(false),
+            generation: Mutex::new(opts.generation),
+            temperature_unit: Mutex::new(opts.temperature_unit),
             opts,
+            last_used: Mutex::new(None),
+            frames: Mutex::new(VecDeque::new()),
+            unparsed: Mutex::new(None),
         })
     }
 
-    #[inline]
-    async fn ensure_connected(&mut self) -> Result<&mut WsStream> {
-        if self.conn.is_none() {
-            self.conn = Some(connect(&self.url).await?);
+    /// The hub's websocket url.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn ensure_connected(&self) -> Result<Arc<Connection>> {
+        if let Some(conn) = self.current() {
+            return Ok(conn);
+        }
+        let _connecting = self.connecting.lock().await;
+        // another task may have connected while we waited
+        if let Some(conn) = self.current() {
+            return Ok(conn);
+        }
+        let transport = self.connect_with_retry().await?;
+        let conn = Arc::new(Connection::new(transport, self.opts.on_event.clone()));
+        *lock(&self.conn) = Some(conn.clone());
+        self.connected_before.store(true, Ordering::SeqCst);
+        self.emit(ConnectionEvent::Connected);
+        Ok(conn)
+    }
+
+    fn emit(&self, event: ConnectionEvent) {
+        if let Some(on_event) = &self.opts.on_event {
+            on_event(event);
+        }
+    }
+
+    // the connection, if it's still usable
+    fn current(&self) -> Option<Arc<Connection>> {
+        let mut current = lock(&self.conn);
+        let conn = current.as_ref()?;
+        if conn.is_poisoned() {
+            debug!("previous command was cancelled while writing, reconnecting");
+            conn.report_closed("a command was cancelled while writing");
+        } else if conn.is_closed() {
+            debug!("connection closed by the hub, reconnecting");
+        } else {
+            return Some(conn.clone());
+        }
+        *current = None;
+        None
+    }
+
+    // connected once, but the connection has since failed
+    pub(crate) fn is_broken(&self) -> bool {
+        lock(&self.conn).is_some() && !self.is_connected()
+    }
+
+    /// Whether we have a usable connection; this doesn't check the hub is still there,
+    /// see [`Client::ping`].
+    pub fn is_connected(&self) -> bool {
+        lock(&self.conn)
+            .as_ref()
+            .is_some_and(|conn| !conn.is_poisoned() && !conn.is_closed())
+    }
+
+    /// Check the connection is alive with a websocket ping (connecting if necessary),
+    /// returning the round-trip time; see [`Transport::ping`].
+    pub async fn ping(&self) -> Result<Duration> {
+        let conn = self.ensure_connected().await?;
+        let start = Instant::now();
+        self.pong(&conn, self.opts.timeout).await?;
+        Ok(start.elapsed())
+    }
+
+    // ping `conn`, and wait up to `deadline` for the pong
+    async fn pong(&self, conn: &Connection, deadline: Duration) -> Result<()> {
+        timeout(deadline, conn.ping())
+            .await
+            .with_context(|| "timeout waiting for pong")??;
+        *lock(&self.last_used) = Some(Instant::now());
+        Ok(())
+    }
+
+    // see `Builder::liveness_check`
+    async fn check_liveness(&self) {
+        let Some(idle) = self.opts.liveness_idle else {
+            return;
+        };
+        let idle_for = lock(&self.last_used).map(|last_used| last_used.elapsed());
+        let Some(conn) = self.current() else {
+            return;
+        };
+        if idle_for.is_none_or(|idle_for| idle_for < idle) {
+            return;
+        }
+        if let Err(e) = self.pong(&conn, self.opts.liveness_deadline).await {
+            debug!(
+                "connection idle for {:?} is dead, reconnecting: {:#}",
+                idle_for, e
+            );
+            let mut current = lock(&self.conn);
+            // unless another task already has
+            if current
+                .as_ref()
+                .is_some_and(|current| Arc::ptr_eq(current, &conn))
+            {
+                *current = None;
+                conn.report_closed(format!("no pong after idling for {:?}", idle_for));
+            }
+        }
+    }
+
+    /// Connect now, rather than on the first command; a no-op if already connected.
+    pub async fn connect(&self) -> Result<()> {
+        self.ensure_connected().await?;
+        Ok(())
+    }
+
+    // see `Builder::connect_retry`
+    async fn connect_with_retry(&self) -> Result<Arc<dyn Transport>> {
+        let mut backoff = self.opts.connect_backoff;
+        let mut attempt = 1;
+        let reconnecting = self.connected_before.load(Ordering::SeqCst);
+        loop {
+            if reconnecting || attempt > 1 {
+                self.emit(ConnectionEvent::Reconnecting { attempt });
+            }
+            let result = timeout(self.opts.timeout, self.connect_once())
+                .await
+                .with_context(|| "timeout connecting")
+                .and_then(|conn| conn);
+            match result {
+                Ok(conn) => return Ok(conn),
+                Err(e) if attempt < self.opts.connect_attempts.unwrap_or(1) => {
+                    warn!(
+                        "connecting failed (attempt {}), retrying in {:?}: {:#}",
+                        attempt, backoff, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn connect_once(&self) -> Result<Arc<dyn Transport>> {
+        match &self.opts.transport {
+            Some(connect) => Ok(Arc::from(connect().await?)),
+            None => Ok(Arc::new(WebSocket::new(
+                connect(&self.url, &self.opts).await?,
+            ))),
+        }
+    }
+
+    /// Send a command, returning the hub's device id and its (json) response.
+    ///
+    /// This, and everything built on it, is cancel-safe: if the future is dropped (e.g. on
+    /// timeout), the late response is discarded when it arrives, or, if the command was only
+    /// partly written, the next command reconnects.
+    pub async fn raw_message(&self, msg: &str) -> Result<(String, String)> {
+        let start = Instant::now();
+        let (outcome, result) = self.dispatch(msg).await;
+        let latency = start.elapsed();
+        if let Some(on_command) = &self.opts.on_command {
+            on_command(&CommandRecord {
+                command: command_name(msg).map(str::to_owned),
+                message: msg.to_string(),
+                outcome,
+                latency,
+            });
+        }
+        if let Ok((_, resp)) = &result {
+            self.retain_frame(resp);
+        }
+        result
+    }
+
+    fn retain_frame(&self, resp: &str) {
+        if self.opts.retain_frames == 0 {
+            return;
+        }
+        let mut frames = lock(&self.frames);
+        if frames.len() == self.opts.retain_frames {
+            frames.pop_front();
+        }
+        frames.push_back(resp.to_string());
+    }
+
+    /// The latest response which couldn't be parsed, as sent, to see why.
+    pub fn last_raw_response(&self) -> Option<String> {
+        lock(&self.unparsed).clone()
+    }
+
+    // keep a response for `last_raw_response`; only on failure, as they can be large
+    pub(crate) fn keep_unparsed(&self, resp: String) {
+        *lock(&self.unparsed) = Some(resp);
+    }
+
+    fn decode<T: DeserializeOwned>(&self, command: &str, resp: String) -> Result<T> {
+        let parsed = decode_response(command, &resp);
+        if parsed.is_err() {
+            self.keep_unparsed(resp);
+        }
+        parsed
+    }
+
+    /// The hub's latest responses, oldest first, see [`Builder::retain_frames`].
+    pub fn raw_frames(&self) -> Vec<String> {
+        lock(&self.frames).iter().cloned().collect()
+    }
+
+    async fn dispatch(&self, msg: &str) -> (Outcome, Result<(String, String)>) {
+        if let Some(guarded) = self.guard(msg) {
+            return guarded;
         }
-        Ok(self.conn.as_mut().expect("we just set it"))
+
+        let result = if self.opts.middleware.is_empty() {
+            self.send_timed(msg).await
+        } else {
+            let middleware = self.opts.middleware.clone();
+            Next::new(self, &middleware).run(msg).await
+        };
+        let outcome = match &result {
+            Ok(_) => Outcome::Sent,
+            Err(e) => Outcome::Failed(format!("{:#}", e)),
+        };
+        (outcome, result)
     }
 
-    pub async fn raw_message(&mut self, msg: &str) -> Result<(String, String)> {
+    // `Some` if the client's settings mean `msg` mustn't be sent as it is
+    fn guard(&self, msg: &str) -> Option<(Outcome, Result<(String, String)>)> {
+        let command = match single_command(msg) {
+            Ok(command) => command,
+            Err(e) => return Some((Outcome::Refused, Err(e))),
+        };
+        if !self.opts.allow_dangerous && commands::is_dangerous(command) {
+            return Some((
+                Outcome::Refused,
+                Err(anyhow!(
+                    "refusing to send a dangerous command, see Builder::allow_dangerous: {}",
+                    msg
+                )),
+            ));
+        }
+        let read_only = commands::is_read_only(command);
+        if self.opts.read_only && !read_only {
+            return Some((
+                Outcome::Refused,
+                Err(anyhow!("client is read-only, refusing to send: {}", msg)),
+            ));
+        }
+        if self.opts.dry_run && !read_only {
+            info!("dry run, not sending: {}", msg);
+            return Some((
+                Outcome::DryRun,
+                Ok((String::new(), DRY_RUN_RESPONSE.to_string())),
+            ));
+        }
+        None
+    }
+
+    async fn send_timed(&self, msg: &str) -> Result<(String, String)> {
+        // again, as middleware may have rewritten the message
+        if let Some((_, result)) = self.guard(msg) {
+            return result;
+        }
+        // outside the timeout, as it may be retrying
+        self.check_liveness().await;
+        self.ensure_connected().await?;
         timeout(self.opts.timeout, self.raw_message_inner(msg))
             .await
             .with_context(|| "timeout sending raw message")?
     }
 
-    async fn raw_message_inner(&mut self, msg: &str) -> Result<(String, String)> {
-        let middle = serde_json::to_string(&json!({
-            "token": self.token,
-            "COMMANDS": [
-                { "COMMAND": msg, "COMMANDID": 1, }
-            ]
-        }))?;
-        let outer = json!({
-            "message_type": "hm_get_command_queue",
-            "message": middle,
-        });
-        let to_send = serde_json::to_string(&outer)?;
-
+    async fn raw_message_inner(&self, msg: &str) -> Result<(String, String)> {
+        let request = self.session.request(msg)?;
         let conn = self.ensure_connected().await?;
-        debug!("sending: {}", to_send);
-
-        conn.feed(Message::Text(to_send)).await?;
-        conn.flush().await?;
+        let pending = conn.expect(request.command_id);
+        debug!("sending: {}", request.frame);
+        conn.send(request.frame).await?;
 
         debug!("receiving");
-        let buf = conn
-            .next()
-            .await
-            .ok_or_else(|| anyhow!("no response received to command"))?
-            .with_context(|| "unpacking websocket message")?
-            .into_data();
-        let resp: CommandResponse =
-            serde_json::from_slice(&buf).with_context(|| "JSON-deserializing response")?;
-        ensure!(
-            resp.message_type == "hm_set_command_response" && resp.command_id == 1,
-            "unexpected response type or id: {:?}",
-            resp
-        );
+        let resp = pending.recv().await?;
+        *lock(&self.last_used) = Some(Instant::now());
         Ok((resp.device_id, resp.response))
     }
 
-    pub async fn command_void<T: DeserializeOwned>(&mut self, command: &str) -> Result<T> {
-        let (_, resp) = self.raw_message(&serialise_void(command)).await?;
-        serde_json::from_str(&resp).with_context(|| anyhow!("reading {:?}", resp))
+    pub async fn command_void<T: DeserializeOwned>(&self, command: &str) -> Result<T> {
+        Ok(self.command_void_response(command).await?.value)
+    }
+
+    pub async fn command_str<T: DeserializeOwned>(&self, command: &str, arg: &str) -> Result<T> {
+        Ok(self.command_str_response(command, arg).await?.value)
+    }
+
+    /// Like [`Client::command_void`], but keeping which hub answered.
+    pub async fn command_void_response<T: DeserializeOwned>(
+        &self,
+        command: &str,
+    ) -> Result<Response<T>> {
+        let (device_id, resp) = self.raw_message(&encode_void(command)).await?;
+        Ok(Response {
+            device_id,
+            value: self.decode(command, resp)?,
+        })
     }
 
-    pub async fn command_str<T: DeserializeOwned>(
-        &mut self,
+    /// Like [`Client::command_str`], but keeping which hub answered.
+    pub async fn command_str_response<T: DeserializeOwned>(
+        &self,
         command: &str,
         arg: &str,
+    ) -> Result<Response<T>> {
+        let (device_id, resp) = self.raw_message(&encode(command, arg)?).await?;
+        Ok(Response {
+            device_id,
+            value: self.decode(command, resp)?,
+        })
+    }
+
+    async fn command_args<T: DeserializeOwned>(
+        &self,
+        command: &str,
+        args: impl Serialize,
     ) -> Result<T> {
-        let (_, resp) = self
-            .raw_message(&format!("{{'{}':'{}'}}", command, arg))
-            .await?;
-        serde_json::from_str(&resp).with_context(|| anyhow!("reading {:?}", resp))
+        let (_, resp) = self.raw_message(&encode(command, args)?).await?;
+        self.decode(command, resp)
+    }
+
+    // commands which change state respond with e.g. {"result": "locked"}
+    async fn command_result(&self, command: &str, args: impl Serialize) -> Result<String> {
+        let resp: commands::Ack = self.command_args(command, args).await?;
+        Ok(resp.result)
+    }
+
+    /// Send any command, for those the typed API doesn't cover yet.
+    ///
+    /// `Value::Null` sends no arguments (as `0`). Strings in the response which hold json,
+    /// as some do, are decoded.
+    pub async fn command_value(&self, command: &str, arg: Value) -> Result<Value> {
+        ensure!(
+            !command.is_empty()
+                && command
+                    .bytes()
+                    .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_'),
+            "not a command name: {:?}",
+            command
+        );
+        let arg = if arg.is_null() { Value::from(0) } else { arg };
+        let mut value: Value = self.command_args(command, arg).await?;
+        decode_nested(&mut value);
+        Ok(value)
     }
 
-    pub async fn identify(&mut self) -> Result<Identity> {
+    /// Send a typed command, see [`commands::Command`].
+    pub async fn send<C: commands::Command>(&self, command: &C) -> Result<C::Output> {
+        self.command_args(C::NAME, command.args()).await
+    }
+
+    pub async fn identify(&self) -> Result<Identity> {
         let (device_id, resp) = self
-            .raw_message(&serialise_void("FIRMWARE"))
+            .raw_message(&encode_void("FIRMWARE"))
             .await
             .with_context(|| "requesting FIRMWARE version")?;
         let firmware: Value = serde_json::from_str(&resp)?;
         Ok(Identity {
             device_id,
-            firmware_version: firmware
-                .get("firmware version")
-                .and_then(|v| v.as_str())
-                .map(str::to_owned),
+            firmware_version: firmware.get("firmware version").and_then(|v| match v {
+                Value::String(version) => Some(version.clone()),
+                Value::Number(version) => Some(version.to_string()),
+                _ => None,
+            }),
         })
     }
 
-    pub async fn disconnect(&mut self) -> Result<()> {
-        let conn = match self.conn.as_mut() {
-            None => return Ok(()),
-            Some(conn) => conn,
-        };
+    /// Check the hub accepts our token, with a cheap command, failing with [`InvalidToken`]
+    /// if it doesn't.
+    pub async fn verify_token(&self) -> Result<()> {
+        let (_, resp) = self.raw_message(&encode_void("FIRMWARE")).await?;
+        let resp: Value = serde_json::from_str(&resp)?;
+        if resp.get("firmware version").is_some() {
+            return Ok(());
+        }
+        let message = ["error", "message"]
+            .iter()
+            .find_map(|key| resp.get(key)?.as_str());
+        match message {
+            Some(message) if message.to_ascii_lowercase().contains("token") => {
+                Err(InvalidToken(message.to_string()).into())
+            }
+            _ => Err(anyhow!("unexpected response to FIRMWARE: {}", resp)),
+        }
+    }
 
-        let shutdown_result = timeout(self.opts.timeout, conn.close(None))
+    pub async fn disconnect(&self) -> Result<()> {
+        let Some(conn) = lock(&self.conn).take() else {
+            return Ok(());
+        };
+        conn.report_closed("disconnected by the client");
+        timeout(self.opts.timeout, conn.close())
             .await
-            .with_context(|| "timeout disconnecting");
-
-        self.conn = None;
-
-        Ok(shutdown_result??)
+            .with_context(|| "timeout disconnecting")?
     }
 }
 
-#[inline]
-fn serialise_void(command: &str) -> String {
-    format!("{{'{}':0}}", command)
+// nothing can panic while holding these locks
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().expect("client poisoned")
 }
 
-#[derive(Deserialize, Debug)]
-struct CommandResponse {
-    // we always send a fixed value (1)
-    command_id: i64,
-
-    // mac-address-like string
-    device_id: String,
-
-    // hm_set_command_response
-    message_type: String,
-
-    // json, in a string
-    response: String,
+// what a state-changing command would usually respond
+const DRY_RUN_RESPONSE: &str = r#"{"result":"dry run"}"#;
+
+// e.g. {"result": "{\"a\": 1}"} to {"result": {"a": 1}}
+fn decode_nested(value: &mut Value) {
+    match value {
+        Value::String(s) if s.starts_with(['{', '[']) => {
+            if let Ok(decoded) = serde_json::from_str::<Value>(s) {
+                *value = decoded;
+                decode_nested(value);
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(decode_nested),
+        Value::Object(map) => map.values_mut().for_each(decode_nested),
+        _ => (),
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct Identity {
+/// A response, and the hub which sent it.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Response<T> {
+    // mac-address-like string, as in `Identity`
     pub device_id: String,
-    pub firmware_version: Option<String>,
+    pub value: T,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-pub struct Profile {
-    // 1-..
-    #[serde(rename = "PROFILE_ID")]
-    pub profile_id: u16,
-    // 0
-    #[serde(rename = "P_TYPE")]
-    pub p_type: u16,
-    pub info: ProfileInfo,
-    pub name: String,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct ProfileInfo {
-    pub monday: ProfileInfoDay,
-    pub tuesday: ProfileInfoDay,
-    pub wednesday: ProfileInfoDay,
-    pub thursday: ProfileInfoDay,
-    pub friday: ProfileInfoDay,
-    pub saturday: ProfileInfoDay,
-    pub sunday: ProfileInfoDay,
+impl<T> Response<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Response<U> {
+        Response {
+            device_id: self.device_id,
+            value: f(self.value),
+        }
+    }
 }
 
-type TempSpec = [Value; 4];
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct ProfileInfoDay {
-    wake: TempSpec,
-    leave: TempSpec,
-    #[serde(rename = "return")]
-    ret: TempSpec,
-    sleep: TempSpec,
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub device_id: String,
+    pub firmware_version: Option<String>,
 }
 
 #[derive(Debug)]
@@ -261,7 +724,18 @@ impl danger::ServerCertVerifier for IgnoreAllCertificateSecurity {
     }
 }
 
-async fn connect(url: &str) -> Result<WsStream> {
+// the library's defaults allow 64MiB messages, far more than any hub sends
+const MAX_MESSAGE_SIZE: usize = 8 << 20;
+
+pub(crate) fn default_websocket_config() -> WebSocketConfig {
+    WebSocketConfig {
+        max_message_size: Some(MAX_MESSAGE_SIZE),
+        max_frame_size: Some(MAX_MESSAGE_SIZE),
+        ..Default::default()
+    }
+}
+
+async fn connect(url: &str, opts: &Opts) -> Result<WsStream> {
     debug!("attempting connection");
     let connector = Connector::Rustls(Arc::new(
         rustls::ClientConfig::builder()
@@ -271,11 +745,43 @@ async fn connect(url: &str) -> Result<WsStream> {
             )))
             .with_no_client_auth(),
     ));
-    let (conn, _) = connect_async_tls_with_config(url, None, true, Some(connector)).await?;
+    let stream = dial(url, opts).await?;
+    let config = opts
+        .websocket_config
+        .unwrap_or_else(default_websocket_config);
+    let (conn, _) =
+        client_async_tls_with_config(url, stream, Some(config), Some(connector)).await?;
     debug!("connected");
     Ok(conn)
 }
 
-fn env_var(key: &'static str) -> Result<String> {
-    std::env::var(key).with_context(|| anyhow!("env var required: {key:?}"))
+// directly, through a proxy, or to a specific address
+async fn dial(url: &str, opts: &Opts) -> Result<TcpStream> {
+    let target = Url::parse(url).with_context(|| anyhow!("parsing url {:?}", url))?;
+    let port = target
+        .port_or_known_default()
+        .ok_or_else(|| anyhow!("url has no port: {:?}", url))?;
+    let host = target
+        .host()
+        .ok_or_else(|| anyhow!("url has no host: {:?}", url))?;
+    // ip addresses, including (bracketed) ipv6 literals, don't need looking up
+    let address = opts.address.or(match host {
+        Host::Ipv4(ip) => Some(SocketAddr::from((ip, port))),
+        Host::Ipv6(ip) => Some(SocketAddr::from((ip, port))),
+        Host::Domain(_) => None,
+    });
+
+    if let Some(proxy) = &opts.proxy {
+        let (host, port) = match address {
+            Some(SocketAddr::V4(address)) => (address.ip().to_string(), address.port()),
+            Some(SocketAddr::V6(address)) => (format!("[{}]", address.ip()), address.port()),
+            None => (host.to_string(), port),
+        };
+        return proxy::connect_via(&Url::parse(proxy)?, &host, port).await;
+    }
+    match (address, host) {
+        (Some(address), _) => TcpStream::connect(address).await,
+        (None, host) => TcpStream::connect((host.to_string(), port)).await,
+    }
+    .with_context(|| anyhow!("connecting to {}", url))
 }
@@ -1,9 +1,20 @@
-use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::time::Duration;
 
-#[derive(Serialize, Deserialize)]
+use log::warn;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+
+use crate::thermostat::parse_hours_minutes;
+use crate::{HcMode, Hold, HubDateTime, TimeZone};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 struct Timestamp(i64);
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 struct Header {
     pub hub_away: bool,
@@ -18,13 +29,17 @@ struct Header {
     pub timestamp_profile_timers: Timestamp,
     pub timestamp_profile_timers_0: Timestamp,
     pub timestamp_recipes: Timestamp,
+    pub timestamp_system: Option<Timestamp>,
 
     pub cool_input: bool,
     pub close_delay: i64,
     pub open_delay: i64,
+
+    #[serde(flatten)]
+    extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub struct Device {
     pub zone_name: String,
@@ -43,9 +58,11 @@ pub struct Device {
     pub cool_temp: f64,
 
     pub actual_temp: String,
+    // 127.5 if there's no floor sensor, see `floor_temperature()`
     pub current_floor_temperature: f64,
     pub prg_temp: i64,
     pub recent_temps: Vec<String>,
+    // 0 if there's no humidity sensor, see `humidity()`
     pub relative_humidity: i64,
     pub set_temp: String,
 
@@ -55,8 +72,9 @@ pub struct Device {
 
     pub fan_control: String,
     pub fan_speed: String,
+    // the floor limit temperature has been reached
     pub floor_limit: bool,
-    pub hc_mode: String,
+    pub hc_mode: HcMode,
     pub heat_mode: bool,
     pub heat_on: bool,
 
@@ -68,6 +86,8 @@ pub struct Device {
 
     pub lock: bool,
     pub low_battery: bool,
+    // only reported by some firmware, see `power_source()`
+    pub battery_powered: Option<bool>,
     pub manual_off: bool,
     pub modelock: bool,
     pub modulation_level: i64,
@@ -82,13 +102,553 @@ pub struct Device {
     pub window_open: bool,
 
     pub thermostat: Option<bool>,
+    pub timeclock: Option<bool>,
+
+    // unix time of the last report from the device; only reported by some firmware
+    pub timestamp: Option<i64>,
+
+    // e.g. "E2", as shown on the stat's display; see `fault()`
+    pub error_code: Option<String>,
 
     pub write_count: i64,
+
+    /// Fields this crate doesn't know about yet, e.g. from newer firmware.
+    ///
+    /// Collecting these means serde buffers each device before reading its fields; see the
+    /// `bench-live-data` example for what that costs.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct LiveData {
+/// A wireless window/door contact sensor.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ContactSensor {
+    pub zone_name: String,
+    pub device_id: i64,
+
+    // the window/door is open
+    pub window_open: bool,
+
+    pub low_battery: bool,
+    pub offline: bool,
+
+    // see `Device::timestamp`
+    pub timestamp: Option<i64>,
+
+    // see `Device::extra`
     #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A zigbee repeater; these are named like `repeaternode12345`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct Repeater {
+    pub zone_name: String,
+    pub device_id: Option<i64>,
+    pub offline: bool,
+
+    // see `Device::timestamp`
+    pub timestamp: Option<i64>,
+
+    // see `Device::extra`
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+const REPEATER_PREFIX: &str = "repeaternode";
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "RawLiveData")]
+pub struct LiveData {
     header: Header,
+    /// Thermostats, timeclocks, etc.
     pub devices: Vec<Device>,
+    pub contact_sensors: Vec<ContactSensor>,
+    pub repeaters: Vec<Repeater>,
+    /// Devices we couldn't make sense of; these don't stop the rest being parsed.
+    pub unparsed: Vec<UnparsedDevice>,
+}
+
+/// When each group of settings last changed, as unix time; `0` if they never have.
+///
+/// These are much cheaper to check than refetching the settings.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamps {
+    /// `GET_ZONES`
+    pub device_lists: i64,
+    /// `GET_ENGINEERS`
+    pub engineers: i64,
+    pub profile_0: i64,
+    /// `GET_PROFILES`
+    pub profile_comfort_levels: i64,
+    pub profile_timers: i64,
+    pub profile_timers_0: i64,
+    /// `GET_RECIPES`
+    pub recipes: i64,
+    /// `GET_SYSTEM`; older firmware doesn't report this.
+    pub system: Option<i64>,
+}
+
+/// A device entry which didn't match any of the device models.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnparsedDevice {
+    pub zone_name: Option<String>,
+    pub error: String,
+    pub raw: Value,
+}
+
+impl UnparsedDevice {
+    fn new(raw: Value, error: serde_json::Error) -> Self {
+        UnparsedDevice {
+            zone_name: raw
+                .get("ZONE_NAME")
+                .and_then(Value::as_str)
+                .map(str::to_owned),
+            error: error.to_string(),
+            raw,
+        }
+    }
+}
+
+// the hub mixes all kinds of devices together in one list; each is kept as text until we
+// know what it is, rather than building a `Value` and trying each type on a clone of it.
+// Each device is still buffered once while parsing, for `Device::extra`'s flatten
+#[derive(Deserialize)]
+struct RawLiveData {
+    #[serde(flatten)]
+    header: Header,
+    devices: Vec<Box<RawValue>>,
+}
+
+// just enough of a device to tell what kind it is
+#[derive(Deserialize)]
+struct Probe<'a> {
+    #[serde(rename = "ZONE_NAME", borrow)]
+    zone_name: Option<Cow<'a, str>>,
+    // thermostats and timeclocks have these; contact sensors don't
+    #[serde(rename = "SET_TEMP")]
+    set_temp: Option<IgnoredAny>,
+    #[serde(rename = "HEAT_ON")]
+    heat_on: Option<IgnoredAny>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum OutDevice<'a> {
+    Device(&'a Device),
+    ContactSensor(&'a ContactSensor),
+    Repeater(&'a Repeater),
+    Unparsed(&'a Value),
+}
+
+impl From<RawLiveData> for LiveData {
+    fn from(raw: RawLiveData) -> Self {
+        let mut devices = Vec::new();
+        let mut contact_sensors = Vec::new();
+        let mut repeaters = Vec::new();
+        let mut unparsed = Vec::new();
+        for raw_device in raw.devices {
+            let text = raw_device.get();
+            let probe = serde_json::from_str::<Probe>(text).ok();
+            let is_repeater = probe
+                .as_ref()
+                .and_then(|probe| probe.zone_name.as_deref())
+                .is_some_and(|name| name.starts_with(REPEATER_PREFIX));
+            let is_sensor = probe
+                .as_ref()
+                .is_some_and(|probe| probe.set_temp.is_none() && probe.heat_on.is_none());
+            let parsed = if is_repeater {
+                serde_json::from_str(text).map(|repeater| repeaters.push(repeater))
+            } else if is_sensor {
+                serde_json::from_str(text).map(|sensor| contact_sensors.push(sensor))
+            } else {
+                serde_json::from_str(text).map(|device| devices.push(device))
+            };
+            if let Err(e) = parsed {
+                let raw = serde_json::from_str(text).unwrap_or(Value::Null);
+                let device = UnparsedDevice::new(raw, e);
+                warn!(
+                    "ignoring unrecognised device {:?}: {}",
+                    device.zone_name, device.error
+                );
+                unparsed.push(device);
+            }
+        }
+        LiveData {
+            header: raw.header,
+            devices,
+            contact_sensors,
+            repeaters,
+            unparsed,
+        }
+    }
+}
+
+impl Serialize for LiveData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Out<'a> {
+            #[serde(flatten)]
+            header: &'a Header,
+            devices: Vec<OutDevice<'a>>,
+        }
+
+        Out {
+            header: &self.header,
+            devices: self
+                .devices
+                .iter()
+                .map(OutDevice::Device)
+                .chain(self.contact_sensors.iter().map(OutDevice::ContactSensor))
+                .chain(self.repeaters.iter().map(OutDevice::Repeater))
+                .chain(
+                    self.unparsed
+                        .iter()
+                        .map(|device| OutDevice::Unparsed(&device.raw)),
+                )
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl LiveData {
+    /// The hub's clock, as unix time.
+    pub fn hub_time(&self) -> i64 {
+        self.header.hub_time.0
+    }
+
+    /// The hub's clock, in its time zone, see [`crate::Client::time_zone`].
+    pub fn hub_datetime(&self, zone: TimeZone) -> HubDateTime {
+        zone.at(self.hub_time())
+    }
+
+    /// When the hub's settings last changed; see [`crate::Cache`].
+    pub fn timestamps(&self) -> Timestamps {
+        let header = &self.header;
+        Timestamps {
+            device_lists: header.timestamp_device_lists.0,
+            engineers: header.timestamp_engineers.0,
+            profile_0: header.timestamp_profile_0.0,
+            profile_comfort_levels: header.timestamp_profile_comfort_levels.0,
+            profile_timers: header.timestamp_profile_timers.0,
+            profile_timers_0: header.timestamp_profile_timers_0.0,
+            recipes: header.timestamp_recipes.0,
+            system: header.timestamp_system.map(|stamp| stamp.0),
+        }
+    }
+
+    /// Top-level fields, like [`Device::extra`].
+    pub fn extra(&self) -> &Map<String, Value> {
+        &self.header.extra
+    }
+
+    /// The kind of every device, sensor and repeater, by zone name.
+    pub fn device_types(&self) -> BTreeMap<&str, DeviceType> {
+        let devices = self
+            .devices
+            .iter()
+            .map(|device| (device.zone_name.as_str(), device.device_type()));
+        let sensors = self
+            .contact_sensors
+            .iter()
+            .map(|sensor| (sensor.zone_name.as_str(), DeviceType::Sensor));
+        let repeaters = self
+            .repeaters
+            .iter()
+            .map(|repeater| (repeater.zone_name.as_str(), DeviceType::Repeater));
+        devices.chain(sensors).chain(repeaters).collect()
+    }
+
+    /// Every device, sensor and repeater, in that order.
+    pub fn all_devices(&self) -> impl Iterator<Item = DeviceRef<'_>> {
+        let devices = self.devices.iter().map(DeviceRef::Device);
+        let sensors = self.contact_sensors.iter().map(DeviceRef::ContactSensor);
+        let repeaters = self.repeaters.iter().map(DeviceRef::Repeater);
+        devices.chain(sensors).chain(repeaters)
+    }
+
+    /// Devices and sensors reporting a low battery.
+    pub fn low_battery_devices(&self) -> Vec<DeviceRef<'_>> {
+        self.all_devices()
+            .filter(|device| device.low_battery())
+            .collect()
+    }
+
+    /// Contact sensors reporting that their window/door is open.
+    pub fn open_windows(&self) -> Vec<&ContactSensor> {
+        self.contact_sensors
+            .iter()
+            .filter(|sensor| sensor.window_open)
+            .collect()
+    }
+
+    /// Repeaters the hub has lost contact with.
+    pub fn offline_repeaters(&self) -> Vec<&Repeater> {
+        self.repeaters
+            .iter()
+            .filter(|repeater| repeater.offline)
+            .collect()
+    }
+
+    /// What's overriding each zone's profile, by zone name.
+    pub fn override_states(&self) -> anyhow::Result<BTreeMap<&str, OverrideState>> {
+        self.devices
+            .iter()
+            .map(|device| Ok((device.zone_name.as_str(), device.override_state()?)))
+            .collect()
+    }
+
+    /// Devices reporting a fault.
+    pub fn faulty_devices(&self) -> Vec<(&Device, DeviceFault)> {
+        self.devices
+            .iter()
+            .filter_map(|device| Some((device, device.fault()?)))
+            .collect()
+    }
+
+    /// Devices, sensors and repeaters the hub has lost contact with.
+    pub fn offline_devices(&self) -> Vec<DeviceRef<'_>> {
+        self.all_devices()
+            .filter(|device| device.offline())
+            .collect()
+    }
+
+    /// Devices which are offline, or haven't reported for longer than `max_age` (by hub time).
+    ///
+    /// Devices which don't report a timestamp are only considered stale if they're offline.
+    pub fn stale_devices(&self, max_age: Duration) -> Vec<DeviceRef<'_>> {
+        let hub_time = self.header.hub_time.0;
+        self.all_devices()
+            .filter(|device| {
+                device.offline()
+                    || device.timestamp().is_some_and(|last| {
+                        hub_time.saturating_sub(last) > max_age.as_secs() as i64
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Any of the things in live data, see [`LiveData::all_devices`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeviceRef<'a> {
+    Device(&'a Device),
+    ContactSensor(&'a ContactSensor),
+    Repeater(&'a Repeater),
+}
+
+impl<'a> DeviceRef<'a> {
+    pub fn zone_name(self) -> &'a str {
+        match self {
+            DeviceRef::Device(device) => &device.zone_name,
+            DeviceRef::ContactSensor(sensor) => &sensor.zone_name,
+            DeviceRef::Repeater(repeater) => &repeater.zone_name,
+        }
+    }
+
+    pub fn offline(self) -> bool {
+        match self {
+            DeviceRef::Device(device) => device.offline,
+            DeviceRef::ContactSensor(sensor) => sensor.offline,
+            DeviceRef::Repeater(repeater) => repeater.offline,
+        }
+    }
+
+    /// Unix time of the device's last report; only reported by some firmware.
+    pub fn timestamp(self) -> Option<i64> {
+        match self {
+            DeviceRef::Device(device) => device.timestamp,
+            DeviceRef::ContactSensor(sensor) => sensor.timestamp,
+            DeviceRef::Repeater(repeater) => repeater.timestamp,
+        }
+    }
+
+    /// Repeaters are mains powered, so never report a low battery.
+    pub fn low_battery(self) -> bool {
+        match self {
+            DeviceRef::Device(device) => device.low_battery,
+            DeviceRef::ContactSensor(sensor) => sensor.low_battery,
+            DeviceRef::Repeater(_) => false,
+        }
+    }
+
+    /// The temperature, for devices which measure one.
+    pub fn temperature(self) -> Option<f64> {
+        match self {
+            DeviceRef::Device(device) => device.actual_temp.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// A fault reported by a device, as opposed to it just being cold.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DeviceFault {
+    /// E0: the built-in air sensor has failed.
+    AirSensor,
+    /// E1: the remote air sensor has failed, or is disconnected.
+    RemoteSensor,
+    /// E2: the floor probe has failed, or is disconnected.
+    FloorSensor,
+    /// A code we don't recognise.
+    Other(String),
+}
+
+impl DeviceFault {
+    pub fn from_code(code: &str) -> Option<DeviceFault> {
+        let code = code.trim();
+        Some(match code.to_ascii_uppercase().as_str() {
+            "" | "0" | "NONE" => return None,
+            "E0" => DeviceFault::AirSensor,
+            "E1" => DeviceFault::RemoteSensor,
+            "E2" => DeviceFault::FloorSensor,
+            _ => DeviceFault::Other(code.to_string()),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PowerSource {
+    Battery,
+    Mains,
+    /// The hub didn't say.
+    Unknown,
+}
+
+/// What kind of device something in live data is, see [`LiveData::device_types`].
+///
+/// [`Device::device_type`] only returns what live data's flags can tell apart: so far,
+/// hot water is just a timeclock, and plugs aren't distinguished from thermostats. The
+/// other variants are for classifying zones yourself, e.g. from their names.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeviceType {
+    /// Heating only.
+    Thermostat,
+    /// Heating and cooling.
+    ThermostatHC,
+    /// `TIMECLOCK`, which may be for hot water.
+    Timeclock,
+    /// A timeclock for hot water; not detected from live data.
+    HotWater,
+    /// A switched mains socket, e.g. a NeoPlug; not detected from live data.
+    Plug,
+    /// A window/door contact sensor.
+    Sensor,
+    Repeater,
+}
+
+impl DeviceType {
+    /// Whether this controls heating, so takes temperatures, holds, standby, etc.
+    pub fn is_thermostat(self) -> bool {
+        matches!(self, DeviceType::Thermostat | DeviceType::ThermostatHC)
+    }
+}
+
+/// Why a zone isn't following its profile, see [`Device::override_state`].
+///
+/// Where several apply, the one the thermostat obeys wins: holiday, then away, standby,
+/// a hold, and a temporary setpoint.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum OverrideState {
+    /// Following its profile.
+    None,
+    /// The hub is in holiday mode.
+    Holiday,
+    /// The hub, or zone, is set to away.
+    Away,
+    /// Frost protection only, see [`crate::Client::standby`].
+    Standby,
+    Hold(Hold),
+    /// The setpoint was changed, until the profile's next comfort level.
+    Temporary {
+        temperature: f64,
+    },
+}
+
+// reported for temperatures from sensors which aren't fitted
+const NO_SENSOR: f64 = 127.5;
+
+impl Device {
+    /// What kind of device this is, from its flags; thermostats unless they say otherwise.
+    pub fn device_type(&self) -> DeviceType {
+        if self.timeclock == Some(true) {
+            DeviceType::Timeclock
+        } else if self.available_modes.iter().any(|mode| mode == "cool") {
+            DeviceType::ThermostatHC
+        } else {
+            DeviceType::Thermostat
+        }
+    }
+
+    /// When the device last reported, in the hub's time zone; only some firmware says.
+    pub fn last_seen(&self, zone: TimeZone) -> Option<HubDateTime> {
+        self.timestamp.map(|unix| zone.at(unix))
+    }
+
+    /// The floor temperature, if the device has a floor sensor.
+    pub fn floor_temperature(&self) -> Option<f64> {
+        if self.current_floor_temperature == NO_SENSOR {
+            None
+        } else {
+            Some(self.current_floor_temperature)
+        }
+    }
+
+    /// Relative humidity (percent), if the device has a humidity sensor.
+    pub fn humidity(&self) -> Option<u8> {
+        match self.relative_humidity {
+            1..=100 => Some(self.relative_humidity as u8),
+            _ => None,
+        }
+    }
+
+    /// The active hold, if any.
+    pub fn hold(&self) -> anyhow::Result<Option<Hold>> {
+        if !self.hold_on {
+            return Ok(None);
+        }
+        Ok(Some(Hold {
+            temperature: self.hold_temp,
+            remaining: parse_hours_minutes(&self.hold_time)?,
+        }))
+    }
+
+    /// What, if anything, is overriding the zone's profile.
+    pub fn override_state(&self) -> anyhow::Result<OverrideState> {
+        if self.holiday {
+            return Ok(OverrideState::Holiday);
+        }
+        if self.away {
+            return Ok(OverrideState::Away);
+        }
+        if self.standby {
+            return Ok(OverrideState::Standby);
+        }
+        if let Some(hold) = self.hold()? {
+            return Ok(OverrideState::Hold(hold));
+        }
+        if self.temporary_set_flag {
+            let temperature =
+                self.set_temp.trim().parse().map_err(|_| {
+                    anyhow::anyhow!("unexpected set temperature: {:?}", self.set_temp)
+                })?;
+            return Ok(OverrideState::Temporary { temperature });
+        }
+        Ok(OverrideState::None)
+    }
+
+    pub fn fault(&self) -> Option<DeviceFault> {
+        self.error_code.as_deref().and_then(DeviceFault::from_code)
+    }
+
+    pub fn power_source(&self) -> PowerSource {
+        match self.battery_powered {
+            Some(true) => PowerSource::Battery,
+            Some(false) => PowerSource::Mains,
+            None => PowerSource::Unknown,
+        }
+    }
 }
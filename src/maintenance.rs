@@ -0,0 +1,58 @@
+use anyhow::{ensure, Result};
+
+use crate::{commands, Client, Zone};
+
+/// Commands which can take a hub offline or undo installation work, see
+/// [`Client::danger_zone`].
+///
+/// These can't be sent at all, even with [`Client::raw_message`], unless the client was
+/// built with [`crate::Builder::allow_dangerous`].
+pub struct DangerZone<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    /// Maintenance commands, if the client was built with
+    /// [`crate::Builder::allow_dangerous`].
+    pub fn danger_zone(&self) -> Result<DangerZone<'_>> {
+        ensure!(
+            self.opts.allow_dangerous,
+            "dangerous commands are disabled, see Builder::allow_dangerous"
+        );
+        Ok(DangerZone { client: self })
+    }
+}
+
+impl DangerZone<'_> {
+    /// Restart the hub, e.g. if it's stopped responding to some commands; the connection
+    /// drops, and the next command reconnects once the hub is back.
+    pub async fn reboot(&self) -> Result<()> {
+        self.client.command_result(commands::RESET, 0).await?;
+        Ok(())
+    }
+
+    /// Remove a zone from the hub; the device will need re-pairing to use it again.
+    pub async fn remove_zone(&self, zone: impl Into<Zone>) -> Result<()> {
+        let zone = self.client.resolve_zones([zone]).await?.remove(0);
+        self.client
+            .command_result(commands::REMOVE_ZONE, zone)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove a repeater, by its name in live data, e.g. `repeaternode12345`.
+    pub async fn remove_repeater(&self, name: &str) -> Result<()> {
+        self.client
+            .command_result(commands::REMOVE_REPEATER, name)
+            .await?;
+        Ok(())
+    }
+
+    /// Forget the hub's HomeKit pairing.
+    pub async fn reset_homekit(&self) -> Result<()> {
+        self.client
+            .command_result(commands::RESET_HOMEKIT, 0)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+
+use crate::Client;
+
+/// Wraps sending a message to the hub, and receiving its `(device_id, response)`.
+///
+/// Implementations can inspect or rewrite the message, call `next.run()` any number of
+/// times (e.g. to retry), or not at all (e.g. to answer from a cache).
+///
+/// ```
+/// use anyhow::Result;
+/// use futures_util::future::BoxFuture;
+/// use neohub::{Middleware, Next};
+///
+/// struct Retry;
+///
+/// impl Middleware for Retry {
+///     fn handle<'a>(
+///         &'a self,
+///         msg: &'a str,
+///         mut next: Next<'a>,
+///     ) -> BoxFuture<'a, Result<(String, String)>> {
+///         Box::pin(async move {
+///             match next.run(msg).await {
+///                 Ok(resp) => Ok(resp),
+///                 Err(_) => next.run(msg).await,
+///             }
+///         })
+///     }
+/// }
+/// ```
+pub trait Middleware: Send + Sync {
+    fn handle<'a>(
+        &'a self,
+        msg: &'a str,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<(String, String)>>;
+}
+
+/// The rest of the pipeline, see [`Middleware`].
+pub struct Next<'a> {
+    client: &'a Client,
+    rest: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(client: &'a Client, middleware: &'a [Arc<dyn Middleware>]) -> Self {
+        Next {
+            client,
+            rest: middleware,
+        }
+    }
+
+    /// Pass the message on to the next middleware, or send it to the hub.
+    pub fn run<'b>(&'b mut self, msg: &'b str) -> BoxFuture<'b, Result<(String, String)>> {
+        match self.rest.split_first() {
+            Some((first, rest)) => first.handle(
+                msg,
+                Next {
+                    client: self.client,
+                    rest,
+                },
+            ),
+            None => Box::pin(self.client.send_timed(msg)),
+        }
+    }
+}
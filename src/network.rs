@@ -0,0 +1,81 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{Client, LiveData};
+
+/// The hub's zigbee network, see [`Client::network_health`].
+///
+/// The hub's API doesn't expose any radio diagnostics (channel, link quality, signal
+/// strength), so this is only what live data says: whether each device is offline, and
+/// when it last reported.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct NetworkHealth {
+    /// Every device the hub knows about, including repeaters and contact sensors.
+    pub links: Vec<Link>,
+}
+
+/// One zigbee device's connection to the hub.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Link {
+    pub zone_name: String,
+    pub device_id: Option<i64>,
+    pub offline: bool,
+    /// Unix time of the device's last report (`TIMESTAMP`); only reported by some firmware.
+    pub last_seen: Option<i64>,
+}
+
+impl NetworkHealth {
+    pub fn from_live_data(live_data: &LiveData) -> Self {
+        let devices = live_data.devices.iter().map(|device| {
+            Link::new(
+                &device.zone_name,
+                Some(device.device_id),
+                device.offline,
+                device.timestamp,
+            )
+        });
+        let sensors = live_data.contact_sensors.iter().map(|sensor| {
+            Link::new(
+                &sensor.zone_name,
+                Some(sensor.device_id),
+                sensor.offline,
+                sensor.timestamp,
+            )
+        });
+        let repeaters = live_data.repeaters.iter().map(|repeater| {
+            Link::new(
+                &repeater.zone_name,
+                repeater.device_id,
+                repeater.offline,
+                repeater.timestamp,
+            )
+        });
+        NetworkHealth {
+            links: devices.chain(sensors).chain(repeaters).collect(),
+        }
+    }
+
+    /// Devices the hub has lost contact with.
+    pub fn offline(&self) -> Vec<&Link> {
+        self.links.iter().filter(|link| link.offline).collect()
+    }
+}
+
+impl Link {
+    fn new(zone_name: &str, device_id: Option<i64>, offline: bool, last_seen: Option<i64>) -> Self {
+        Link {
+            zone_name: zone_name.to_string(),
+            device_id,
+            offline,
+            last_seen,
+        }
+    }
+}
+
+impl Client {
+    /// The state of the zigbee network, from live data.
+    pub async fn network_health(&self) -> Result<NetworkHealth> {
+        let live_data = self.live_data().await?;
+        Ok(NetworkHealth::from_live_data(&live_data))
+    }
+}
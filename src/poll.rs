@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{ensure, Result};
+use futures_util::stream::{self, Stream};
+use futures_util::StreamExt;
+use log::warn;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::{Client, DeviceRef, HcMode, LiveData};
+
+impl Client {
+    /// Fetch [`Client::live_data`] every `period`, starting immediately.
+    ///
+    /// Errors are yielded, not fatal; the stream never ends. Panics if `period` is zero.
+    pub fn poll_live_data(
+        &self,
+        period: Duration,
+    ) -> impl Stream<Item = Result<LiveData>> + Send + '_ {
+        let mut ticks = interval(period);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        stream::unfold((self, ticks), |(client, mut ticks)| async move {
+            ticks.tick().await;
+            let live_data = client.live_data().await;
+            Some((live_data, (client, ticks)))
+        })
+    }
+}
+
+/// One poll's live data, as sent to [`Poller`] subscribers.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub live_data: Arc<LiveData>,
+    /// Zones whose device, sensor or repeater changed state (temperatures, demand, mode,
+    /// holds, windows, being offline) since the previous snapshot; every zone, for the first.
+    pub changed: Vec<String>,
+}
+
+/// Polls live data in a background task, sharing each snapshot with any number of
+/// subscribers, so they only cost the hub one poll.
+///
+/// Polling errors are logged and skipped. Polling stops when this is dropped.
+pub struct Poller {
+    tx: broadcast::Sender<Snapshot>,
+    latest: Arc<Mutex<Option<Snapshot>>>,
+    task: JoinHandle<()>,
+}
+
+// snapshots a slow subscriber can fall behind by, before it misses some
+const CAPACITY: usize = 16;
+
+impl Poller {
+    /// Start polling every `period`; see [`Client::poll_live_data`].
+    pub fn spawn(client: impl Into<Arc<Client>>, period: Duration) -> Result<Poller> {
+        ensure!(!period.is_zero(), "can't poll every 0s");
+        let client = client.into();
+        let (tx, _) = broadcast::channel(CAPACITY);
+        let latest = Arc::new(Mutex::new(None::<Snapshot>));
+        let task = tokio::spawn({
+            let tx = tx.clone();
+            let latest = latest.clone();
+            async move {
+                let mut polls = Box::pin(client.poll_live_data(period));
+                let mut previous: Option<Arc<LiveData>> = None;
+                while let Some(live_data) = polls.next().await {
+                    let live_data = match live_data {
+                        Ok(live_data) => Arc::new(live_data),
+                        Err(e) => {
+                            warn!("poller: polling failed: {:#}", e);
+                            continue;
+                        }
+                    };
+                    let snapshot = Snapshot {
+                        changed: changed_zones(previous.as_deref(), &live_data),
+                        live_data: live_data.clone(),
+                    };
+                    previous = Some(live_data);
+                    *latest.lock().expect("poller poisoned") = Some(snapshot.clone());
+                    // fine if nobody's subscribed yet
+                    drop(tx.send(snapshot));
+                }
+            }
+        });
+        Ok(Poller { tx, latest, task })
+    }
+
+    /// Receive every snapshot from now on; a subscriber which falls too far behind gets
+    /// `RecvError::Lagged`, and skips to the oldest it can still have.
+    pub fn subscribe(&self) -> broadcast::Receiver<Snapshot> {
+        self.tx.subscribe()
+    }
+
+    /// The most recent snapshot, e.g. for a subscriber which has just joined.
+    pub fn latest(&self) -> Option<Snapshot> {
+        self.latest.lock().expect("poller poisoned").clone()
+    }
+}
+
+impl Drop for Poller {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// what a subscriber cares about; not e.g. the device's clock, or its write count
+#[derive(PartialEq)]
+enum State<'a> {
+    Device {
+        actual_temp: &'a str,
+        set_temp: &'a str,
+        heat_on: bool,
+        cool_on: bool,
+        hc_mode: HcMode,
+        away: bool,
+        holiday: bool,
+        standby: bool,
+        hold_on: bool,
+        hold_temp: f64,
+        temporary_set_flag: bool,
+        window_open: bool,
+        offline: bool,
+    },
+    Sensor {
+        window_open: bool,
+        offline: bool,
+    },
+    Repeater {
+        offline: bool,
+    },
+}
+
+impl<'a> State<'a> {
+    fn of(device: DeviceRef<'a>) -> Self {
+        match device {
+            DeviceRef::Device(device) => State::Device {
+                actual_temp: &device.actual_temp,
+                set_temp: &device.set_temp,
+                heat_on: device.heat_on,
+                cool_on: device.cool_on,
+                hc_mode: device.hc_mode,
+                away: device.away,
+                holiday: device.holiday,
+                standby: device.standby,
+                hold_on: device.hold_on,
+                hold_temp: device.hold_temp,
+                temporary_set_flag: device.temporary_set_flag,
+                window_open: device.window_open,
+                offline: device.offline,
+            },
+            DeviceRef::ContactSensor(sensor) => State::Sensor {
+                window_open: sensor.window_open,
+                offline: sensor.offline,
+            },
+            DeviceRef::Repeater(repeater) => State::Repeater {
+                offline: repeater.offline,
+            },
+        }
+    }
+}
+
+fn changed_zones(previous: Option<&LiveData>, current: &LiveData) -> Vec<String> {
+    let before: HashMap<&str, State> = previous
+        .map(|previous| {
+            previous
+                .all_devices()
+                .map(|device| (device.zone_name(), State::of(device)))
+                .collect()
+        })
+        .unwrap_or_default();
+    current
+        .all_devices()
+        .filter(|device| before.get(device.zone_name()) != Some(&State::of(*device)))
+        .map(|device| device.zone_name().to_string())
+        .collect()
+}
@@ -0,0 +1,69 @@
+use std::ops::Deref;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use log::debug;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{Builder, Client};
+
+/// Up to `size` clients (so connections) to a hub, for sending commands in parallel.
+///
+/// Clients are built (and connect) as they're needed, and reused once they're returned;
+/// any whose connection has failed are thrown away.
+pub struct Pool {
+    builder: Builder,
+    idle: Mutex<Vec<Client>>,
+    permits: Semaphore,
+}
+
+/// A client checked out of a [`Pool`], returned to it on drop.
+pub struct Pooled<'a> {
+    pool: &'a Pool,
+    client: Option<Client>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Pool {
+    pub fn new(builder: Builder, size: usize) -> Self {
+        Pool {
+            builder,
+            idle: Mutex::new(Vec::new()),
+            permits: Semaphore::new(size.max(1)),
+        }
+    }
+
+    /// Check out a client, waiting until one is free.
+    pub async fn get(&self) -> Result<Pooled<'_>> {
+        let permit = self.permits.acquire().await?;
+        let idle = self.idle.lock().expect("pool poisoned").pop();
+        let client = match idle {
+            Some(client) => client,
+            None => self.builder.clone().build()?,
+        };
+        Ok(Pooled {
+            pool: self,
+            client: Some(client),
+            _permit: permit,
+        })
+    }
+}
+
+impl Deref for Pooled<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().expect("only taken on drop")
+    }
+}
+
+impl Drop for Pooled<'_> {
+    fn drop(&mut self) {
+        let client = self.client.take().expect("only taken on drop");
+        if client.is_broken() {
+            debug!("discarding pooled client with a failed connection");
+            return;
+        }
+        self.pool.idle.lock().expect("pool poisoned").push(client);
+    }
+}
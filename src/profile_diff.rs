@@ -0,0 +1,145 @@
+use std::fmt;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+
+use crate::{Client, Day, Event, Profile};
+
+/// One difference between two profiles, see [`diff_profiles`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum ProfileChange {
+    Renamed {
+        from: String,
+        to: String,
+    },
+    Time {
+        day: Day,
+        event: Event,
+        from: String,
+        to: String,
+    },
+    Temperature {
+        day: Day,
+        event: Event,
+        from: f64,
+        to: f64,
+    },
+}
+
+/// What writing a local profile would change on the hub.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ProfileDiff {
+    /// The hub has no profile with this name, so it would be created.
+    pub created: bool,
+    pub changes: Vec<ProfileChange>,
+}
+
+impl ProfileDiff {
+    pub fn is_empty(&self) -> bool {
+        !self.created && self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for ProfileChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileChange::Renamed { from, to } => write!(f, "name: {:?} -> {:?}", from, to),
+            ProfileChange::Time {
+                day,
+                event,
+                from,
+                to,
+            } => write!(f, "{:?} {:?} time: {} -> {}", day, event, from, to),
+            ProfileChange::Temperature {
+                day,
+                event,
+                from,
+                to,
+            } => write!(f, "{:?} {:?} temperature: {} -> {}", day, event, from, to),
+        }
+    }
+}
+
+/// One change per line.
+impl fmt::Display for ProfileDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.created {
+            writeln!(f, "new profile")?;
+        }
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// What would change if `hub` were replaced with `local`, by day and event.
+///
+/// Only names, times and temperatures are compared; ids are per-hub, and the hub's other
+/// comfort level fields are undocumented.
+pub fn diff_profiles(local: &Profile, hub: &Profile) -> ProfileDiff {
+    let mut changes = Vec::new();
+    if local.name != hub.name {
+        changes.push(ProfileChange::Renamed {
+            from: hub.name.clone(),
+            to: local.name.clone(),
+        });
+    }
+    for day in Day::ALL {
+        for event in Event::ALL {
+            let from = hub.info.day(day).event(event);
+            let to = local.info.day(day).event(event);
+            if from.time != to.time {
+                changes.push(ProfileChange::Time {
+                    day,
+                    event,
+                    from: from.time.clone(),
+                    to: to.time.clone(),
+                });
+            }
+            if from.temperature != to.temperature {
+                changes.push(ProfileChange::Temperature {
+                    day,
+                    event,
+                    from: from.temperature,
+                    to: to.temperature,
+                });
+            }
+        }
+    }
+    ProfileDiff {
+        created: false,
+        changes,
+    }
+}
+
+impl Client {
+    /// Store `profile`, matching the hub's profiles by name, unless the hub already has it
+    /// as written; returns what changed, which is empty if nothing was written.
+    pub async fn store_if_changed(&self, profile: &Profile) -> Result<ProfileDiff> {
+        let existing = self.profiles().await.context("reading profiles")?;
+        let mut profile = profile.clone();
+        let diff = match existing.get(&profile.name) {
+            Some(current) => {
+                let diff = diff_profiles(&profile, current);
+                if diff.is_empty() {
+                    return Ok(diff);
+                }
+                // ids are per-hub
+                profile.profile_id = current.profile_id;
+                diff
+            }
+            None => {
+                profile.profile_id = 0;
+                ProfileDiff {
+                    created: true,
+                    changes: Vec::new(),
+                }
+            }
+        };
+        self.store_profile(&profile)
+            .await
+            .with_context(|| anyhow!("storing {:?}", profile.name))?;
+        Ok(diff)
+    }
+}
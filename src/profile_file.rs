@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{ComfortLevel, Day, Profile, ProfileInfo, ProfileInfoDay};
+
+// bump this if the file format changes incompatibly
+const FILE_VERSION: u32 = 1;
+
+// The on-disk format, which is deliberately not the hub's format. It has no hub-specific
+// ids, so can be shared between installations:
+//
+// {
+//   "version": 1,
+//   "name": "Winter",
+//   "days": {
+//     "monday": {
+//       "wake": { "time": "06:30", "temperature": 20.0 },
+//       ...
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileFile {
+    version: u32,
+    name: String,
+    days: BTreeMap<Day, FileDay>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct FileDay {
+    wake: FileLevel,
+    leave: FileLevel,
+    #[serde(rename = "return")]
+    ret: FileLevel,
+    sleep: FileLevel,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct FileLevel {
+    time: String,
+    temperature: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extra: Vec<Value>,
+}
+
+impl Profile {
+    /// Write the profile in a stable JSON format, suitable for version control.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file = ProfileFile {
+            version: FILE_VERSION,
+            name: self.name.clone(),
+            days: Day::ALL
+                .into_iter()
+                .map(|day| (day, FileDay::from(self.info.day(day))))
+                .collect(),
+        };
+        let mut json = serde_json::to_string_pretty(&file)?;
+        json.push('\n');
+        fs::write(path, json).with_context(|| anyhow!("writing profile to {:?}", path))
+    }
+
+    /// Read a profile written by [`Profile::to_file`].
+    ///
+    /// The returned profile has a `profile_id` of `0`, as ids are specific to a hub.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Profile> {
+        let path = path.as_ref();
+        let json =
+            fs::read_to_string(path).with_context(|| anyhow!("reading profile from {:?}", path))?;
+        let file: ProfileFile = serde_json::from_str(&json)
+            .with_context(|| anyhow!("parsing profile from {:?}", path))?;
+        ensure!(
+            file.version == FILE_VERSION,
+            "unsupported profile file version {} in {:?}",
+            file.version,
+            path
+        );
+        let profile = file.into_profile()?;
+        profile
+            .check_times()
+            .with_context(|| anyhow!("in {:?}", path))?;
+        Ok(profile)
+    }
+}
+
+impl ProfileFile {
+    fn into_profile(mut self) -> Result<Profile> {
+        let mut day = |day: Day| {
+            self.days
+                .remove(&day)
+                .map(ProfileInfoDay::from)
+                .ok_or_else(|| anyhow!("missing day: {:?}", day))
+        };
+        Ok(Profile {
+            profile_id: 0,
+            p_type: 0,
+            info: ProfileInfo {
+                monday: day(Day::Monday)?,
+                tuesday: day(Day::Tuesday)?,
+                wednesday: day(Day::Wednesday)?,
+                thursday: day(Day::Thursday)?,
+                friday: day(Day::Friday)?,
+                saturday: day(Day::Saturday)?,
+                sunday: day(Day::Sunday)?,
+            },
+            name: self.name,
+            extra: Map::new(),
+        })
+    }
+}
+
+impl From<&ProfileInfoDay> for FileDay {
+    fn from(day: &ProfileInfoDay) -> Self {
+        FileDay {
+            wake: FileLevel::from(&day.wake),
+            leave: FileLevel::from(&day.leave),
+            ret: FileLevel::from(&day.ret),
+            sleep: FileLevel::from(&day.sleep),
+        }
+    }
+}
+
+impl From<FileDay> for ProfileInfoDay {
+    fn from(day: FileDay) -> Self {
+        ProfileInfoDay {
+            wake: day.wake.into(),
+            leave: day.leave.into(),
+            ret: day.ret.into(),
+            sleep: day.sleep.into(),
+        }
+    }
+}
+
+impl From<&ComfortLevel> for FileLevel {
+    fn from(level: &ComfortLevel) -> Self {
+        FileLevel {
+            time: level.time.clone(),
+            temperature: level.temperature,
+            extra: level.extra.clone(),
+        }
+    }
+}
+
+impl From<FileLevel> for ComfortLevel {
+    fn from(level: FileLevel) -> Self {
+        ComfortLevel {
+            time: level.time,
+            temperature: level.temperature,
+            extra: level.extra,
+        }
+    }
+}
+
+// "HH:MM" to minutes past midnight
+pub(crate) fn parse_time(time: &str) -> Result<u16> {
+    let (hours, minutes) = time
+        .split_once(':')
+        .ok_or_else(|| anyhow!("expected HH:MM, not {:?}", time))?;
+    let hours: u16 = hours
+        .parse()
+        .with_context(|| anyhow!("bad hours in {:?}", time))?;
+    let minutes: u16 = minutes
+        .parse()
+        .with_context(|| anyhow!("bad minutes in {:?}", time))?;
+    ensure!(hours < 24 && minutes < 60, "time out of range: {:?}", time);
+    Ok(hours * 60 + minutes)
+}
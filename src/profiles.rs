@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{commands, Client, Zone};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Profile {
+    // 1-..
+    #[serde(rename = "PROFILE_ID")]
+    pub profile_id: u16,
+    // 0
+    #[serde(rename = "P_TYPE")]
+    pub p_type: u16,
+    pub info: ProfileInfo,
+    pub name: String,
+
+    // see `Device::extra`
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A stored profile, by name (as the app shows them) or by id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProfileRef {
+    Name(String),
+    Id(u16),
+}
+
+impl From<&str> for ProfileRef {
+    fn from(name: &str) -> Self {
+        ProfileRef::Name(name.to_string())
+    }
+}
+
+impl From<&String> for ProfileRef {
+    fn from(name: &String) -> Self {
+        ProfileRef::Name(name.clone())
+    }
+}
+
+impl From<String> for ProfileRef {
+    fn from(name: String) -> Self {
+        ProfileRef::Name(name)
+    }
+}
+
+impl From<u16> for ProfileRef {
+    fn from(id: u16) -> Self {
+        ProfileRef::Id(id)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ProfileInfo {
+    pub monday: ProfileInfoDay,
+    pub tuesday: ProfileInfoDay,
+    pub wednesday: ProfileInfoDay,
+    pub thursday: ProfileInfoDay,
+    pub friday: ProfileInfoDay,
+    pub saturday: ProfileInfoDay,
+    pub sunday: ProfileInfoDay,
+}
+
+/// A day of the week, as used in profiles.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Day {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Day {
+    pub const ALL: [Day; 7] = [
+        Day::Monday,
+        Day::Tuesday,
+        Day::Wednesday,
+        Day::Thursday,
+        Day::Friday,
+        Day::Saturday,
+        Day::Sunday,
+    ];
+    pub const WEEKDAYS: [Day; 5] = [
+        Day::Monday,
+        Day::Tuesday,
+        Day::Wednesday,
+        Day::Thursday,
+        Day::Friday,
+    ];
+    pub const WEEKEND: [Day; 2] = [Day::Saturday, Day::Sunday];
+}
+
+impl ProfileInfo {
+    pub fn day(&self, day: Day) -> &ProfileInfoDay {
+        match day {
+            Day::Monday => &self.monday,
+            Day::Tuesday => &self.tuesday,
+            Day::Wednesday => &self.wednesday,
+            Day::Thursday => &self.thursday,
+            Day::Friday => &self.friday,
+            Day::Saturday => &self.saturday,
+            Day::Sunday => &self.sunday,
+        }
+    }
+
+    pub fn day_mut(&mut self, day: Day) -> &mut ProfileInfoDay {
+        match day {
+            Day::Monday => &mut self.monday,
+            Day::Tuesday => &mut self.tuesday,
+            Day::Wednesday => &mut self.wednesday,
+            Day::Thursday => &mut self.thursday,
+            Day::Friday => &mut self.friday,
+            Day::Saturday => &mut self.saturday,
+            Day::Sunday => &mut self.sunday,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ProfileInfoDay {
+    pub wake: ComfortLevel,
+    pub leave: ComfortLevel,
+    #[serde(rename = "return")]
+    pub ret: ComfortLevel,
+    pub sleep: ComfortLevel,
+}
+
+/// At `time` ("HH:MM"), heat to `temperature`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "TempSpec", into = "TempSpec")]
+pub struct ComfortLevel {
+    pub time: String,
+    pub temperature: f64,
+    // the hub sends more, e.g. ["07:00", 21, 4, true]; it's undocumented, so we just keep it
+    pub extra: Vec<Value>,
+}
+
+type TempSpec = Vec<Value>;
+
+impl TryFrom<TempSpec> for ComfortLevel {
+    type Error = anyhow::Error;
+
+    fn try_from(spec: TempSpec) -> Result<Self> {
+        let mut spec = spec.into_iter();
+        let time = spec
+            .next()
+            .and_then(|v| v.as_str().map(str::to_owned))
+            .ok_or_else(|| anyhow!("comfort level must start with a time"))?;
+        let temperature = spec
+            .next()
+            .and_then(|v| v.as_f64())
+            .ok_or_else(|| anyhow!("comfort level must have a temperature"))?;
+        Ok(ComfortLevel {
+            time,
+            temperature,
+            extra: spec.collect(),
+        })
+    }
+}
+
+impl From<ComfortLevel> for TempSpec {
+    fn from(level: ComfortLevel) -> Self {
+        let mut spec = vec![Value::from(level.time), Value::from(level.temperature)];
+        spec.extend(level.extra);
+        spec
+    }
+}
+
+impl Client {
+    /// Stored profiles, by name.
+    pub async fn profiles(&self) -> Result<BTreeMap<String, Profile>> {
+        self.command_checked(commands::GET_PROFILES).await
+    }
+
+    /// Stored profiles' ids, by name.
+    ///
+    /// From `GET_PROFILES`, as `GET_PROFILE_NAMES` is deprecated.
+    pub async fn profile_names(&self) -> Result<BTreeMap<String, u16>> {
+        Ok(self
+            .profiles()
+            .await?
+            .into_values()
+            .map(|profile| (profile.name, profile.profile_id))
+            .collect())
+    }
+
+    pub async fn profile(&self, profile: impl Into<ProfileRef>) -> Result<Profile> {
+        let profile = profile.into();
+        let profiles = self.profiles().await?;
+        profiles
+            .into_values()
+            .find(|candidate| match &profile {
+                ProfileRef::Name(name) => &candidate.name == name,
+                ProfileRef::Id(id) => candidate.profile_id == *id,
+            })
+            .ok_or_else(|| anyhow!("profile not found: {:?}", profile))
+    }
+
+    /// Delete a stored profile.
+    pub async fn delete_profile(&self, profile: impl Into<ProfileRef>) -> Result<()> {
+        let id = self.resolve_profile(profile).await?;
+        self.command_result(commands::CLEAR_PROFILE_ID, id).await?;
+        Ok(())
+    }
+
+    /// Run a stored profile on the given zones, replacing whatever they were running.
+    pub async fn run_profile(
+        &self,
+        profile: impl Into<ProfileRef>,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let id = self.resolve_profile(profile).await?;
+        let zones = self.resolve_zones(zones).await?;
+        let mut args = vec![Value::from(id)];
+        args.extend(zones.into_iter().map(Value::from));
+        self.command_result(commands::RUN_PROFILE_ID, args).await?;
+        Ok(())
+    }
+
+    /// The id of the profile each zone is running, by zone name; `None` if it isn't
+    /// running one (e.g. it's on its own schedule).
+    pub async fn active_profiles(&self) -> Result<BTreeMap<String, Option<u16>>> {
+        let live_data = self.live_data().await?;
+        live_data
+            .devices
+            .into_iter()
+            .map(|device| {
+                let id = match device.active_profile {
+                    0 => None,
+                    id => Some(
+                        u16::try_from(id).map_err(|_| anyhow!("unexpected profile id {}", id))?,
+                    ),
+                };
+                Ok((device.zone_name, id))
+            })
+            .collect()
+    }
+
+    // a profile's id, only asking the hub if we were given a name
+    pub(crate) async fn resolve_profile(&self, profile: impl Into<ProfileRef>) -> Result<u16> {
+        match profile.into() {
+            ProfileRef::Id(id) => Ok(id),
+            ProfileRef::Name(name) => self
+                .profile_names()
+                .await?
+                .remove(&name)
+                .ok_or_else(|| anyhow!("no profile named {:?}", name)),
+        }
+    }
+
+    /// Store a profile, replacing any profile with the same id; an id of `0` creates a new one.
+    pub async fn store_profile(&self, profile: &Profile) -> Result<()> {
+        self.command_result(commands::STORE_PROFILE2, store_args(profile))
+            .await?;
+        Ok(())
+    }
+}
+
+pub(crate) fn store_args(profile: &Profile) -> Value {
+    if profile.profile_id == 0 {
+        json!({ "name": profile.name, "info": profile.info })
+    } else {
+        json!({ "ID": profile.profile_id, "name": profile.name, "info": profile.info })
+    }
+}
@@ -0,0 +1,224 @@
+//! The hub's wire protocol, without any I/O: encoding commands, wrapping them in the
+//! command queue envelope, and reading what comes back.
+//!
+//! [`crate::Client`] uses this over a [`crate::Transport`], by default a websocket; see
+//! [`crate::Server`] for the hub's side.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error;
+
+/// Numbers commands for one connection's worth of requests, so responses can be matched up.
+#[derive(Debug)]
+pub struct Session {
+    token: String,
+    // responses with an older id belong to commands which were cancelled
+    next_command_id: AtomicI64,
+}
+
+/// A frame to send, see [`Session::request`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub command_id: i64,
+    pub frame: String,
+}
+
+/// The hub's response to one command.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommandResponse {
+    // echoes the COMMANDID we sent; these increase, per client
+    pub command_id: i64,
+
+    // mac-address-like string
+    pub device_id: String,
+
+    // hm_set_command_response
+    pub message_type: String,
+
+    // json, in a string
+    pub response: String,
+}
+
+/// A frame received from the hub, see [`decode_frame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Response(CommandResponse),
+    /// An error the hub sent without saying which command it was for, e.g. for a bad token.
+    Error(String),
+    /// Something other than a command response, which can be ignored.
+    Unsolicited(CommandResponse),
+    /// Not anything we recognise.
+    Unreadable(String),
+}
+
+/// The commands in a frame sent to the hub, see [`decode_request`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct CommandQueue {
+    pub token: String,
+    #[serde(rename = "COMMANDS")]
+    pub commands: Vec<QueuedCommand>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct QueuedCommand {
+    /// e.g. `{'FIRMWARE':0}`
+    #[serde(rename = "COMMAND")]
+    pub command: String,
+    #[serde(rename = "COMMANDID")]
+    pub command_id: Value,
+}
+
+// the outer layer of what's sent to the hub; `message` holds a `CommandQueue`, as json
+#[derive(Deserialize)]
+struct Outer {
+    message: String,
+}
+
+impl Session {
+    pub fn new(token: impl ToString) -> Self {
+        Session {
+            token: token.to_string(),
+            next_command_id: AtomicI64::new(1),
+        }
+    }
+
+    /// Wrap `msg` (e.g. `{'FIRMWARE':0}`) in the envelope the hub expects, with a new
+    /// command id.
+    pub fn request(&self, msg: &str) -> Result<Request> {
+        let command_id = self.next_command_id.fetch_add(1, Ordering::SeqCst);
+        let middle = serde_json::to_string(&json!({
+            "token": self.token,
+            "COMMANDS": [
+                { "COMMAND": msg, "COMMANDID": command_id, }
+            ]
+        }))?;
+        let outer = json!({
+            "message_type": "hm_get_command_queue",
+            "message": middle,
+        });
+        Ok(Request {
+            command_id,
+            frame: serde_json::to_string(&outer)?,
+        })
+    }
+}
+
+/// Read a frame received from the hub.
+pub fn decode_frame(buf: &[u8]) -> Frame {
+    let resp: CommandResponse = match serde_json::from_slice(buf) {
+        Ok(resp) => resp,
+        Err(e) => {
+            return match error_message(buf) {
+                Some(message) => Frame::Error(message),
+                None => Frame::Unreadable(e.to_string()),
+            }
+        }
+    };
+    if resp.message_type == "hm_set_command_response" {
+        Frame::Response(resp)
+    } else {
+        Frame::Unsolicited(resp)
+    }
+}
+
+// e.g. {"message_type": "error", "message": "Invalid token"}
+fn error_message(buf: &[u8]) -> Option<String> {
+    let value: Value = serde_json::from_slice(buf).ok()?;
+    if value.get("command_id").is_some() {
+        return None;
+    }
+    ["error", "message"]
+        .iter()
+        .find_map(|key| value.get(key)?.as_str())
+        .map(str::to_owned)
+}
+
+/// Read a frame sent to the hub, e.g. to act as one.
+pub fn decode_request(frame: &str) -> Result<CommandQueue> {
+    serde_json::from_str::<Outer>(frame)
+        .and_then(|outer| serde_json::from_str(&outer.message))
+        .with_context(|| anyhow!("unreadable command queue: {:?}", frame))
+}
+
+/// The hub's response to the command with `command_id`, as it would send it.
+pub fn encode_response(command_id: &Value, device_id: &str, response: &str) -> String {
+    json!({
+        "command_id": command_id,
+        "device_id": device_id,
+        "message_type": "hm_set_command_response",
+        "response": response,
+    })
+    .to_string()
+}
+
+/// An error which isn't for any one command, as the hub would send it.
+pub fn encode_error(message: &str) -> String {
+    json!({"message_type": "error", "message": message}).to_string()
+}
+
+/// A command without arguments, e.g. `{'FIRMWARE':0}`.
+#[inline]
+pub fn encode_void(command: &str) -> String {
+    format!("{{'{}':0}}", command)
+}
+
+/// A command with arguments, e.g. `{'SET_TEMP':[20,['Office']]}`.
+///
+/// The hub wants single-quoted "json", so this fails if any strings contain quotes; see
+/// [`check_encodable`].
+pub fn encode(command: &str, args: impl Serialize) -> Result<String> {
+    let args = serde_json::to_string(&args)?;
+    // checked as sent, rather than via `to_value`, which could reorder fields
+    check_encodable(&serde_json::from_str(&args)?)
+        .with_context(|| anyhow!("encoding {}", command))?;
+    Ok(format!("{{'{}':{}}}", command, args.replace('"', "'")))
+}
+
+/// Strings are sent single-quoted, without escaping, so can't contain quotes.
+pub fn check_encodable(value: &Value) -> Result<()> {
+    match value {
+        Value::String(s) => ensure!(
+            !s.contains(['\'', '"']),
+            "the hub can't be sent strings containing quotes: {:?}",
+            s
+        ),
+        Value::Array(values) => values.iter().try_for_each(check_encodable)?,
+        Value::Object(map) => map.iter().try_for_each(|(k, v)| {
+            check_encodable(&Value::from(k.as_str())).and(check_encodable(v))
+        })?,
+        _ => (),
+    }
+    Ok(())
+}
+
+/// The key of a message like `{'GET_LIVE_DATA':0}`.
+pub fn command_name(msg: &str) -> Option<&str> {
+    let msg = msg.trim_start().strip_prefix('{')?.trim_start();
+    let quote = msg.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let msg = &msg[1..];
+    msg.find(quote).map(|end| &msg[..end])
+}
+
+/// The name of the only command in `msg`, failing if there's more than one, e.g.
+/// `{'GET_ZONES':0,'RESET':0}`, or it isn't a command at all.
+pub fn single_command(msg: &str) -> Result<&str> {
+    // strings can't contain quotes, see `check_encodable`, so this is json
+    let commands: BTreeMap<String, IgnoredAny> = serde_json::from_str(&msg.replace('\'', "\""))
+        .with_context(|| anyhow!("not a command: {}", msg))?;
+    ensure!(commands.len() == 1, "not a single command: {}", msg);
+    command_name(msg).ok_or_else(|| anyhow!("not a command: {}", msg))
+}
+
+/// Parse a response to `command`, failing with [`crate::HubRejected`] if the hub refused it.
+pub fn decode_response<T: DeserializeOwned>(command: &str, resp: &str) -> Result<T> {
+    if let Some(rejected) = error::rejection(command, resp) {
+        return Err(rejected.into());
+    }
+    serde_json::from_str(resp).with_context(|| anyhow!("reading {:?}", resp))
+}
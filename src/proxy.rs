@@ -0,0 +1,158 @@
+use std::net::SocketAddr;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use log::debug;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{lookup_host, TcpStream};
+use url::Url;
+
+/// Open a tunnel to `host:port` through an `http://` (CONNECT), `socks5://` or `socks5h://`
+/// proxy; only `socks5` looks the host up locally, like curl.
+pub(crate) async fn connect_via(proxy: &Url, host: &str, port: u16) -> Result<TcpStream> {
+    let proxy_host = proxy
+        .host_str()
+        .ok_or_else(|| anyhow!("proxy url has no host: {}", proxy))?;
+    let proxy_port = proxy
+        .port_or_known_default()
+        .or(match proxy.scheme() {
+            "socks5" | "socks5h" => Some(1080),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("proxy url has no port: {}", proxy))?;
+    debug!("connecting to proxy {}:{}", proxy_host, proxy_port);
+    let mut stream = TcpStream::connect((proxy_host, proxy_port))
+        .await
+        .with_context(|| anyhow!("connecting to proxy {}", proxy))?;
+    let credentials = match proxy.username() {
+        "" => None,
+        user => Some((user, proxy.password().unwrap_or(""))),
+    };
+
+    match proxy.scheme() {
+        "http" => http_connect(&mut stream, host, port, credentials).await,
+        "socks5" => {
+            let address = resolve(host, port).await?;
+            socks5_connect(&mut stream, &address.ip().to_string(), port, credentials).await
+        }
+        "socks5h" => socks5_connect(&mut stream, host, port, credentials).await,
+        other => bail!(
+            "unsupported proxy scheme {:?}, expected http, socks5 or socks5h",
+            other
+        ),
+    }
+    .with_context(|| anyhow!("tunnelling through proxy {}", proxy))?;
+    Ok(stream)
+}
+
+async fn resolve(host: &str, port: u16) -> Result<SocketAddr> {
+    let host = host.trim_matches(|c| c == '[' || c == ']');
+    lookup_host((host, port))
+        .await
+        .with_context(|| anyhow!("looking up {}", host))?
+        .next()
+        .ok_or_else(|| anyhow!("no addresses for {}", host))
+}
+
+async fn http_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> Result<()> {
+    let target = format!("{host}:{port}");
+    let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+    if let Some((user, password)) = credentials {
+        let token = data_encoding::BASE64.encode(format!("{user}:{password}").as_bytes());
+        request.push_str(&format!("Proxy-Authorization: Basic {token}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // read byte-by-byte, so we don't consume anything after the headers
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        ensure!(response.len() < 8192, "proxy response headers too long");
+        let b = stream.read_u8().await.context("reading proxy response")?;
+        response.push(b);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status = response.lines().next().unwrap_or_default();
+    ensure!(
+        status.split_whitespace().nth(1) == Some("200"),
+        "proxy refused: {}",
+        status
+    );
+    Ok(())
+}
+
+async fn socks5_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> Result<()> {
+    // greeting: version 5, offering "no auth", or username/password
+    let method = if credentials.is_some() { 2 } else { 0 };
+    stream.write_all(&[5, 1, method]).await?;
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply).await?;
+    ensure!(reply[0] == 5, "not a socks5 proxy");
+    ensure!(
+        reply[1] == method,
+        "proxy wants an authentication method we don't support ({})",
+        reply[1]
+    );
+
+    if let Some((user, password)) = credentials {
+        ensure!(
+            user.len() <= 255 && password.len() <= 255,
+            "proxy credentials too long"
+        );
+        let mut auth = vec![1, user.len() as u8];
+        auth.extend(user.as_bytes());
+        auth.push(password.len() as u8);
+        auth.extend(password.as_bytes());
+        stream.write_all(&auth).await?;
+        stream.read_exact(&mut reply).await?;
+        ensure!(reply[1] == 0, "proxy rejected our credentials");
+    }
+
+    // connect, by address, or by name so the proxy does the dns lookup
+    let mut request = vec![5, 1, 0];
+    match host.trim_matches(|c| c == '[' || c == ']').parse() {
+        Ok(std::net::IpAddr::V4(ip)) => {
+            request.push(1);
+            request.extend(ip.octets());
+        }
+        Ok(std::net::IpAddr::V6(ip)) => {
+            request.push(4);
+            request.extend(ip.octets());
+        }
+        Err(_) => {
+            ensure!(host.len() <= 255, "host name too long for socks5");
+            request.push(3);
+            request.push(host.len() as u8);
+            request.extend(host.as_bytes());
+        }
+    }
+    request.extend(port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).await?;
+    ensure!(
+        reply[1] == 0,
+        "proxy couldn't connect (socks5 error {})",
+        reply[1]
+    );
+    // skip the bound address, which we don't need
+    let address_len = match reply[3] {
+        1 => 4,
+        4 => 16,
+        3 => stream.read_u8().await? as usize,
+        other => bail!("unexpected socks5 address type {}", other),
+    };
+    let mut bound = vec![0; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{commands, Client};
+
+/// A recipe stored on the hub, from `GET_RECIPES`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Recipe {
+    pub name: String,
+    // whatever the hub will run; the format isn't documented
+    pub steps: Value,
+}
+
+impl Client {
+    pub async fn recipes(&self) -> Result<Vec<Recipe>> {
+        let recipes: BTreeMap<String, Value> = self.command_void(commands::GET_RECIPES).await?;
+        Ok(recipes
+            .into_iter()
+            .map(|(name, steps)| Recipe { name, steps })
+            .collect())
+    }
+
+    pub async fn run_recipe(&self, name: &str) -> Result<()> {
+        self.command_result(commands::RUN_RECIPE, [name]).await?;
+        Ok(())
+    }
+}
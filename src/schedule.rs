@@ -0,0 +1,293 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::profile_file::parse_time;
+use crate::{ComfortLevel, Day, Profile, ProfileInfoDay};
+
+/// The events in each day of a profile, in order.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    Wake,
+    Leave,
+    Return,
+    Sleep,
+}
+
+impl Event {
+    pub const ALL: [Event; 4] = [Event::Wake, Event::Leave, Event::Return, Event::Sleep];
+}
+
+/// How the hub applies profiles to days, from the `FORMAT` in `GET_SYSTEM`.
+///
+/// Some firmware reports the format as the number of schedules, e.g. `2` for `"5DAY"`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "RawFormat")]
+pub enum ScheduleFormat {
+    #[serde(rename = "NONPROGRAMMABLE")]
+    NonProgrammable,
+    /// Monday's schedule is used every day.
+    #[serde(rename = "24HOURSFIXED")]
+    SameEveryDay,
+    /// Monday's schedule is used on weekdays, and Saturday's at weekends.
+    #[serde(rename = "5DAY")]
+    WeekdaysWeekend,
+    #[serde(rename = "7DAY")]
+    SevenDay,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFormat {
+    Name(String),
+    Schedules(u64),
+}
+
+impl TryFrom<RawFormat> for ScheduleFormat {
+    type Error = String;
+
+    fn try_from(raw: RawFormat) -> Result<Self, String> {
+        match raw {
+            RawFormat::Name(name) => match name.as_str() {
+                "NONPROGRAMMABLE" => Ok(ScheduleFormat::NonProgrammable),
+                "24HOURSFIXED" => Ok(ScheduleFormat::SameEveryDay),
+                "5DAY" => Ok(ScheduleFormat::WeekdaysWeekend),
+                "7DAY" => Ok(ScheduleFormat::SevenDay),
+                _ => Err(format!("unknown schedule format {:?}", name)),
+            },
+            RawFormat::Schedules(0) => Ok(ScheduleFormat::NonProgrammable),
+            RawFormat::Schedules(1) => Ok(ScheduleFormat::SameEveryDay),
+            RawFormat::Schedules(2) => Ok(ScheduleFormat::WeekdaysWeekend),
+            RawFormat::Schedules(7) => Ok(ScheduleFormat::SevenDay),
+            RawFormat::Schedules(n) => Err(format!("unknown schedule format {}", n)),
+        }
+    }
+}
+
+impl ScheduleFormat {
+    /// The day of the profile which applies on `day`.
+    pub fn profile_day(self, day: Day) -> Option<Day> {
+        match self {
+            ScheduleFormat::NonProgrammable => None,
+            ScheduleFormat::SameEveryDay => Some(Day::Monday),
+            ScheduleFormat::WeekdaysWeekend => Some(match day {
+                Day::Saturday | Day::Sunday => Day::Saturday,
+                _ => Day::Monday,
+            }),
+            ScheduleFormat::SevenDay => Some(day),
+        }
+    }
+}
+
+impl Day {
+    pub fn next(self) -> Day {
+        Day::ALL[(self as usize + 1) % Day::ALL.len()]
+    }
+
+    pub fn previous(self) -> Day {
+        Day::ALL[(self as usize + Day::ALL.len() - 1) % Day::ALL.len()]
+    }
+}
+
+impl FromStr for Day {
+    type Err = anyhow::Error;
+
+    /// As in live data's `DATE`, e.g. "tuesday".
+    fn from_str(s: &str) -> Result<Day> {
+        Day::ALL
+            .into_iter()
+            .find(|day| format!("{day:?}").eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| anyhow!("not a day: {:?}", s))
+    }
+}
+
+impl ProfileInfoDay {
+    pub fn event(&self, event: Event) -> &ComfortLevel {
+        match event {
+            Event::Wake => &self.wake,
+            Event::Leave => &self.leave,
+            Event::Return => &self.ret,
+            Event::Sleep => &self.sleep,
+        }
+    }
+
+    pub fn event_mut(&mut self, event: Event) -> &mut ComfortLevel {
+        match event {
+            Event::Wake => &mut self.wake,
+            Event::Leave => &mut self.leave,
+            Event::Return => &mut self.ret,
+            Event::Sleep => &mut self.sleep,
+        }
+    }
+}
+
+/// A scheduled change, see [`Profile::setpoints_at`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Setpoint {
+    /// The day this happens, which isn't necessarily the day of the profile it's from.
+    pub day: Day,
+    pub event: Event,
+    /// "HH:MM"
+    pub time: String,
+    pub temperature: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Setpoints {
+    /// The most recent change, i.e. what's in effect now.
+    pub current: Setpoint,
+    pub next: Setpoint,
+}
+
+/// A period of one day during which the profile asks for a single temperature.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Minutes past midnight; the last segment of a day ends at 1440.
+    pub minutes: Range<u16>,
+    pub temperature: f64,
+    /// The event which started this; the first segment of a day continues the previous
+    /// day's last event.
+    pub event: Event,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct DayTimeline {
+    pub day: Day,
+    pub segments: Vec<Segment>,
+}
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+impl Profile {
+    /// Change when an event happens on `day`, and the temperature it sets; `time` is "HH:MM".
+    ///
+    /// This doesn't check the events are still in order, see [`Profile::validate`].
+    pub fn set_event(
+        &mut self,
+        day: Day,
+        event: Event,
+        time: &str,
+        temperature: f64,
+    ) -> Result<()> {
+        parse_time(time)?;
+        let level = self.info.day_mut(day).event_mut(event);
+        level.time = time.to_string();
+        level.temperature = temperature;
+        Ok(())
+    }
+
+    /// [`Profile::set_event`] on each of `days`.
+    pub fn apply_to(
+        &mut self,
+        days: impl IntoIterator<Item = Day>,
+        event: Event,
+        time: &str,
+        temperature: f64,
+    ) -> Result<()> {
+        for day in days {
+            self.set_event(day, event, time, temperature)?;
+        }
+        Ok(())
+    }
+
+    /// E.g. make every weekday wake at 06:30, at 20 degrees.
+    pub fn apply_to_weekdays(&mut self, event: Event, time: &str, temperature: f64) -> Result<()> {
+        self.apply_to(Day::WEEKDAYS, event, time, temperature)
+    }
+
+    pub fn apply_to_weekend(&mut self, event: Event, time: &str, temperature: f64) -> Result<()> {
+        self.apply_to(Day::WEEKEND, event, time, temperature)
+    }
+
+    /// The profile as a week of segments, Monday first, covering every minute of every day.
+    pub fn timeline(&self, format: ScheduleFormat) -> Result<Vec<DayTimeline>> {
+        Day::ALL
+            .into_iter()
+            .map(|day| self.day_timeline(format, day))
+            .collect()
+    }
+
+    pub fn day_timeline(&self, format: ScheduleFormat, day: Day) -> Result<DayTimeline> {
+        let today = self.events_on(format, day)?;
+        let (_, overnight) = self
+            .events_on(format, day.previous())?
+            .pop()
+            .expect("four events");
+
+        let mut segments = Vec::with_capacity(today.len() + 1);
+        let mut start = 0;
+        let mut current = (overnight.event, overnight.temperature);
+        for (minutes, setpoint) in today {
+            if minutes > start {
+                segments.push(Segment {
+                    minutes: start..minutes,
+                    temperature: current.1,
+                    event: current.0,
+                });
+            }
+            start = minutes;
+            current = (setpoint.event, setpoint.temperature);
+        }
+        segments.push(Segment {
+            minutes: start..MINUTES_PER_DAY,
+            temperature: current.1,
+            event: current.0,
+        });
+        Ok(DayTimeline { day, segments })
+    }
+
+    /// What the profile says is happening at `time` ("HH:MM") on `day`, and what's next.
+    ///
+    /// This is what the profile says, not what the stat is doing; holds etc. aren't considered.
+    pub fn setpoints_at(&self, format: ScheduleFormat, day: Day, time: &str) -> Result<Setpoints> {
+        if format == ScheduleFormat::NonProgrammable {
+            bail!("non-programmable hubs don't have schedules");
+        }
+        let now = parse_time(time)?;
+
+        let today = self.events_on(format, day)?;
+        let current = match today.iter().rev().find(|(minutes, _)| *minutes <= now) {
+            Some((_, setpoint)) => setpoint.clone(),
+            // before today's first event, so yesterday's last is still in effect
+            None => {
+                self.events_on(format, day.previous())?
+                    .pop()
+                    .expect("four events")
+                    .1
+            }
+        };
+        let next = match today.iter().find(|(minutes, _)| *minutes > now) {
+            Some((_, setpoint)) => setpoint.clone(),
+            None => self.events_on(format, day.next())?.swap_remove(0).1,
+        };
+        Ok(Setpoints { current, next })
+    }
+
+    // (minutes past midnight, setpoint), sorted by time
+    fn events_on(&self, format: ScheduleFormat, day: Day) -> Result<Vec<(u16, Setpoint)>> {
+        let profile_day = format
+            .profile_day(day)
+            .ok_or_else(|| anyhow!("{:?} has no schedule", format))?;
+        let info = self.info.day(profile_day);
+        let mut events = Event::ALL
+            .into_iter()
+            .map(|event| {
+                let level = info.event(event);
+                Ok((
+                    parse_time(&level.time)?,
+                    Setpoint {
+                        day,
+                        event,
+                        time: level.time.clone(),
+                        temperature: level.temperature,
+                    },
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        events.sort_by_key(|(minutes, _)| *minutes);
+        Ok(events)
+    }
+}
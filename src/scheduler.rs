@@ -0,0 +1,267 @@
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{civil_from_days, unix_now};
+use crate::profile_file::parse_time;
+use crate::{Client, Day, TimeZone};
+
+// bump this if the file format changes incompatibly
+const FILE_VERSION: u32 = 1;
+
+// if we were asleep for longer than this, don't catch up on everything we missed
+const MAX_CATCH_UP_MINUTES: i64 = 24 * 60;
+
+// how often `Scheduler::run` rereads the hub's time zone, for daylight saving
+const TIME_ZONE_REFRESH: Duration = Duration::from_secs(3600);
+
+/// Recurring actions, for automations the hub's own profiles can't express, e.g. "all
+/// standby on the 1st of the month"; see [`Scheduler::run`].
+///
+/// Times are in the hub's time zone. Save the jobs with [`Scheduler::save`], so they
+/// survive restarts.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Scheduler {
+    jobs: Vec<Job>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    /// Unique within a scheduler.
+    pub name: String,
+    pub when: Recurrence,
+    pub action: Action,
+}
+
+/// When a [`Job`] runs.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Recurrence {
+    /// At `time` ("HH:MM") on each of `days`.
+    Weekly { days: Vec<Day>, time: String },
+    /// At `time` ("HH:MM") on `day` (1-31) of each month; months without that day are
+    /// skipped.
+    Monthly { day: u32, time: String },
+}
+
+/// What a [`Job`] does.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Action {
+    /// See [`Client::set_temperature`].
+    SetTemperature {
+        temperature: f64,
+        zones: Vec<String>,
+    },
+    /// See [`Client::standby`].
+    Standby { zones: Vec<String> },
+    /// See [`Client::resume`].
+    Resume { zones: Vec<String> },
+    /// See [`Client::all_standby`].
+    AllStandby,
+    /// See [`Client::all_resume`].
+    AllResume,
+    /// See [`Client::run_profile`].
+    RunProfile { profile: String, zones: Vec<String> },
+    /// Any command, e.g. `{'FIRMWARE':0}`; see [`Client::raw_message`].
+    Raw { message: String },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct SchedulerFile {
+    version: u32,
+    jobs: Vec<Job>,
+}
+
+impl Recurrence {
+    pub fn daily(time: impl ToString) -> Self {
+        Self::on(Day::ALL, time)
+    }
+
+    /// Monday to Friday.
+    pub fn weekdays(time: impl ToString) -> Self {
+        Self::on(Day::WEEKDAYS, time)
+    }
+
+    pub fn on(days: impl IntoIterator<Item = Day>, time: impl ToString) -> Self {
+        Recurrence::Weekly {
+            days: days.into_iter().collect(),
+            time: time.to_string(),
+        }
+    }
+
+    pub fn monthly(day: u32, time: impl ToString) -> Self {
+        Recurrence::Monthly {
+            day,
+            time: time.to_string(),
+        }
+    }
+
+    fn time(&self) -> &str {
+        match self {
+            Recurrence::Weekly { time, .. } | Recurrence::Monthly { time, .. } => time,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        parse_time(self.time())?;
+        match self {
+            Recurrence::Weekly { days, .. } => ensure!(!days.is_empty(), "no days given"),
+            Recurrence::Monthly { day, .. } => {
+                ensure!((1..=31).contains(day), "no day {} in a month", day)
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether this is due at `minute`, counted from 1970-01-01 00:00 local time.
+    fn is_due(&self, minute: i64) -> bool {
+        let days = minute.div_euclid(24 * 60);
+        let since_midnight = minute.rem_euclid(24 * 60);
+        if parse_time(self.time()).ok().map(i64::from) != Some(since_midnight) {
+            return false;
+        }
+        match self {
+            Recurrence::Weekly { days: on, .. } => {
+                // 1970-01-01 was a Thursday
+                let weekday = Day::ALL[(days + 3).rem_euclid(7) as usize];
+                on.contains(&weekday)
+            }
+            Recurrence::Monthly { day, .. } => civil_from_days(days).2 == *day,
+        }
+    }
+}
+
+impl Action {
+    pub async fn run(&self, client: &Client) -> Result<()> {
+        match self {
+            Action::SetTemperature { temperature, zones } => {
+                client.set_temperature(*temperature, zones).await
+            }
+            Action::Standby { zones } => client.standby(zones).await,
+            Action::Resume { zones } => client.resume(zones).await,
+            Action::AllStandby => client.all_standby().await.map(drop),
+            Action::AllResume => client.all_resume().await.map(drop),
+            Action::RunProfile { profile, zones } => {
+                client.run_profile(profile.as_str(), zones).await
+            }
+            Action::Raw { message } => client.raw_message(message).await.map(drop),
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    /// Add a job, failing if one with the same name already exists or its time is invalid.
+    pub fn add(&mut self, name: impl ToString, when: Recurrence, action: Action) -> Result<()> {
+        let name = name.to_string();
+        ensure!(
+            self.jobs.iter().all(|job| job.name != name),
+            "there's already a job called {:?}",
+            name
+        );
+        when.validate()
+            .with_context(|| anyhow!("scheduling {:?}", name))?;
+        self.jobs.push(Job { name, when, action });
+        Ok(())
+    }
+
+    /// Remove the job called `name`, returning whether there was one.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.jobs.len();
+        self.jobs.retain(|job| job.name != name);
+        self.jobs.len() != before
+    }
+
+    /// Jobs due after unix time `after`, up to and including `until`, in the order they
+    /// fall due.
+    ///
+    /// Jobs are due at the start of their minute; a gap of more than a day only looks
+    /// back a day.
+    pub fn due(&self, zone: TimeZone, after: i64, until: i64) -> Vec<&Job> {
+        let offset = i64::from(zone.offset_seconds);
+        let first = (after + offset).div_euclid(60) + 1;
+        let last = (until + offset).div_euclid(60);
+        let first = first.max(last - MAX_CATCH_UP_MINUTES + 1);
+        (first..=last)
+            .flat_map(|minute| self.jobs.iter().filter(move |job| job.when.is_due(minute)))
+            .collect()
+    }
+
+    /// Run jobs as they fall due, forever.
+    ///
+    /// Failing jobs are logged, and tried again at their next time; only failing to read
+    /// the hub's time zone at the start is an error.
+    pub async fn run(&self, client: &Client) -> Result<()> {
+        let mut zone = client.time_zone().await?;
+        let mut zone_read = SystemTime::now();
+        let mut last = unix_now();
+        loop {
+            // wake just after the start of the next minute
+            let wait = 60 - last.rem_euclid(60);
+            tokio::time::sleep(Duration::from_secs(wait as u64)).await;
+            if zone_read.elapsed().unwrap_or_default() >= TIME_ZONE_REFRESH {
+                match client.time_zone().await {
+                    Ok(read) => zone = read,
+                    Err(e) => warn!("scheduler: keeping time zone {:?}: {:#}", zone, e),
+                }
+                zone_read = SystemTime::now();
+            }
+            let now = unix_now();
+            for job in self.due(zone, last, now) {
+                match job.action.run(client).await {
+                    Ok(()) => info!("scheduler: ran {:?}", job.name),
+                    Err(e) => warn!("scheduler: {:?} failed: {:#}", job.name, e),
+                }
+            }
+            last = now;
+        }
+    }
+
+    /// Write the jobs as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file = SchedulerFile {
+            version: FILE_VERSION,
+            jobs: self.jobs.clone(),
+        };
+        let mut json = serde_json::to_string_pretty(&file)?;
+        json.push('\n');
+        fs::write(path, json).with_context(|| anyhow!("writing schedule to {:?}", path))
+    }
+
+    /// Read jobs written by [`Scheduler::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .with_context(|| anyhow!("reading schedule from {:?}", path))?;
+        let file: SchedulerFile = serde_json::from_str(&json)
+            .with_context(|| anyhow!("parsing schedule from {:?}", path))?;
+        ensure!(
+            file.version == FILE_VERSION,
+            "unsupported schedule file version {} in {:?}",
+            file.version,
+            path
+        );
+        let mut scheduler = Scheduler::new();
+        for job in file.jobs {
+            scheduler
+                .add(job.name, job.when, job.action)
+                .with_context(|| anyhow!("in {:?}", path))?;
+        }
+        Ok(scheduler)
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use serde_json::json;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{protocol, Client};
+
+/// Share one connection to the hub between many local clients, e.g. other tools on the
+/// network, as the hub copes badly with lots of connections.
+///
+/// Local clients speak the hub's protocol, over plain `ws://`. Each local client's commands
+/// are sent upstream in the order they arrive; different clients' share the connection,
+/// see [`Client`].
+pub struct Server {
+    client: Arc<Client>,
+    token: String,
+}
+
+impl Server {
+    /// Only accept commands from local clients using `token`, which isn't the hub's own
+    /// token; anyone with it has full control of the hub.
+    pub fn new(client: impl Into<Arc<Client>>, token: impl ToString) -> Self {
+        Server {
+            client: client.into(),
+            token: token.to_string(),
+        }
+    }
+
+    /// Accept local clients until the listener fails.
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        let token = Arc::new(self.token);
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            info!("proxying for {}", peer);
+            let client = self.client.clone();
+            let token = token.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_one(stream, &client, &token).await {
+                    warn!("proxying for {} failed: {:#}", peer, e);
+                }
+            });
+        }
+    }
+}
+
+async fn serve_one(stream: TcpStream, client: &Client, token: &str) -> Result<()> {
+    let mut ws = tokio_tungstenite::accept_async(stream).await?;
+    while let Some(msg) = ws.next().await {
+        let msg = match msg? {
+            Message::Text(msg) => msg,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let queue = match protocol::decode_request(&msg) {
+            Ok(queue) => queue,
+            Err(e) => {
+                debug!("{:#}", e);
+                let error = protocol::encode_error(&format!("{:#}", e));
+                ws.send(Message::Text(error)).await?;
+                continue;
+            }
+        };
+        if !same_token(&queue.token, token) {
+            let error = protocol::encode_error("Invalid token");
+            ws.send(Message::Text(error)).await?;
+            continue;
+        }
+
+        for queued in queue.commands {
+            debug!("proxying: {}", queued.command);
+            let result = client.raw_message(&queued.command).await;
+            let (device_id, response) = match result {
+                Ok(ok) => ok,
+                // as the hub reports errors, see `HubRejected`
+                Err(e) => (
+                    String::new(),
+                    json!({ "error": format!("{:#}", e) }).to_string(),
+                ),
+            };
+            let resp = protocol::encode_response(&queued.command_id, &device_id, &response);
+            ws.send(Message::Text(resp)).await?;
+        }
+    }
+    Ok(())
+}
+
+// in time independent of where they differ, so the token can't be guessed a byte at a time
+fn same_token(given: &str, token: &str) -> bool {
+    let (given, token) = (given.as_bytes(), token.as_bytes());
+    given.len() == token.len()
+        && given
+            .iter()
+            .zip(token)
+            .fold(0, |differ, (a, b)| differ | (a ^ b))
+            == 0
+}
@@ -0,0 +1,53 @@
+use anyhow::{ensure, Result};
+
+use crate::{commands, Client, ScheduleFormat, TemperatureUnit};
+
+// hub-wide settings, e.g. for provisioning a new hub; the API has no commands for the
+// thermostats' clock format or display brightness, which can only be changed on them
+impl Client {
+    /// Show (and expect) temperatures in `unit`, on the hub and every thermostat.
+    pub async fn set_temperature_unit(&self, unit: TemperatureUnit) -> Result<()> {
+        self.command_result(commands::SET_TEMP_FORMAT, unit.corf())
+            .await?;
+        // in a dry run, the hub still uses the old unit
+        if !self.opts.dry_run {
+            *self.temperature_unit.lock().expect("client poisoned") = Some(unit);
+        }
+        Ok(())
+    }
+
+    /// How heating profiles apply to days; see [`Client::schedule_format`].
+    pub async fn set_schedule_format(&self, format: ScheduleFormat) -> Result<()> {
+        self.command_result(commands::SET_FORMAT, format).await?;
+        Ok(())
+    }
+
+    /// How timeclock profiles apply to days.
+    pub async fn set_timer_format(&self, format: ScheduleFormat) -> Result<()> {
+        self.command_result(commands::SET_TIMER_FORMAT, format)
+            .await?;
+        Ok(())
+    }
+
+    /// Set the hub's offset from UTC, in hours (e.g. `5.5`), not including daylight saving.
+    pub async fn set_time_zone(&self, hours: f64) -> Result<()> {
+        ensure!(
+            (-12.0..=14.0).contains(&hours) && (hours * 4.0).fract() == 0.0,
+            "not a time zone offset: {} hours",
+            hours
+        );
+        self.command_result(commands::TIME_ZONE, hours).await?;
+        Ok(())
+    }
+
+    /// Whether the hub sets its clock from the internet.
+    pub async fn set_ntp(&self, enabled: bool) -> Result<()> {
+        let command = if enabled {
+            commands::NTP_ON
+        } else {
+            commands::NTP_OFF
+        };
+        self.command_result(command, 0).await?;
+        Ok(())
+    }
+}
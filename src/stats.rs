@@ -0,0 +1,143 @@
+//! Heating duty cycles, accumulated from polled [`LiveData`], e.g. for estimating each
+//! room's energy use.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::LiveData;
+
+/// How much of a window a zone spent calling for heat.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DutyCycle {
+    pub window: Duration,
+    pub heat_on: Duration,
+    /// How much of the window we have snapshots for; less than the window until we've been
+    /// polling for that long, or if polling stopped for a while.
+    pub observed: Duration,
+}
+
+impl DutyCycle {
+    /// Heat-on time as a fraction (0-1) of the observed time, if anything was observed.
+    pub fn fraction(&self) -> Option<f64> {
+        if self.observed.is_zero() {
+            None
+        } else {
+            Some(self.heat_on.as_secs_f64() / self.observed.as_secs_f64())
+        }
+    }
+}
+
+#[derive(Default)]
+struct Zone {
+    // (hub time, heat on), oldest first; only as far back as the longest window needs
+    samples: VecDeque<(i64, bool)>,
+    total_heat_on: Duration,
+}
+
+/// Accumulates each zone's heat-on time from successive [`LiveData`] snapshots.
+///
+/// A zone is assumed to stay as it was in one snapshot until the next, unless they're
+/// further apart than the maximum gap, when the time between them isn't counted.
+pub struct DutyCycles {
+    windows: Vec<Duration>,
+    max_gap: Duration,
+    zones: BTreeMap<String, Zone>,
+    // hub time of the latest snapshot
+    now: Option<i64>,
+}
+
+impl DutyCycles {
+    /// Report duty cycles over each of `windows`, e.g. the last hour and the last day.
+    pub fn new(windows: impl IntoIterator<Item = Duration>) -> Self {
+        DutyCycles {
+            windows: windows.into_iter().collect(),
+            max_gap: Duration::from_secs(300),
+            zones: BTreeMap::new(),
+            now: None,
+        }
+    }
+
+    /// The longest time between snapshots to count (default 5 minutes); set this to a few
+    /// polling periods.
+    pub fn max_gap(mut self, max_gap: Duration) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /// Update with a new snapshot; snapshots at or before the latest one are ignored.
+    pub fn observe(&mut self, live_data: &LiveData) {
+        let now = live_data.hub_time();
+        if self.now.is_some_and(|latest| now <= latest) {
+            return;
+        }
+        self.now = Some(now);
+        let keep_from = now - self.longest_window();
+        let max_gap = self.max_gap.as_secs() as i64;
+        for device in &live_data.devices {
+            let zone = self.zones.entry(device.zone_name.clone()).or_default();
+            if let Some(&(then, true)) = zone.samples.back() {
+                zone.total_heat_on += Duration::from_secs((now - then).min(max_gap) as u64);
+            }
+            zone.samples.push_back((now, device.heat_on));
+            // the oldest sample we need is the last one before the window starts
+            while zone
+                .samples
+                .get(1)
+                .is_some_and(|&(time, _)| time <= keep_from)
+            {
+                zone.samples.pop_front();
+            }
+        }
+    }
+
+    /// Each zone's duty cycle over each window, ending at the latest snapshot, by zone name.
+    pub fn report(&self) -> BTreeMap<&str, Vec<DutyCycle>> {
+        self.zones
+            .keys()
+            .filter_map(|name| Some((name.as_str(), self.zone(name)?)))
+            .collect()
+    }
+
+    /// A zone's duty cycle over each window, in the order they were given.
+    pub fn zone(&self, zone: &str) -> Option<Vec<DutyCycle>> {
+        let samples = &self.zones.get(zone)?.samples;
+        let now = self.now?;
+        let max_gap = self.max_gap.as_secs() as i64;
+        Some(
+            self.windows
+                .iter()
+                .map(|&window| {
+                    let start = now - window.as_secs() as i64;
+                    let mut heat_on = 0;
+                    let mut observed = 0;
+                    for (&(from, on), &(to, _)) in samples.iter().zip(samples.iter().skip(1)) {
+                        let span = (to.min(from + max_gap) - from.max(start)).max(0);
+                        observed += span;
+                        if on {
+                            heat_on += span;
+                        }
+                    }
+                    DutyCycle {
+                        window,
+                        heat_on: Duration::from_secs(heat_on as u64),
+                        observed: Duration::from_secs(observed as u64),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// How long a zone has called for heat, in total, since the first snapshot.
+    pub fn total_heat_on(&self, zone: &str) -> Option<Duration> {
+        Some(self.zones.get(zone)?.total_heat_on)
+    }
+
+    fn longest_window(&self) -> i64 {
+        self.windows
+            .iter()
+            .max()
+            .map_or(0, |window| window.as_secs() as i64)
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::{Client, ContactSensor, Device, EngineersData, LiveData, Profile, Repeater};
+
+/// Types which keep fields they don't recognise, see [`crate::Builder::strict`].
+pub trait UnknownFields {
+    /// Paths to fields which aren't modelled, e.g. `devices[Office].NEW_THING`.
+    fn unknown_fields(&self) -> Vec<String> {
+        let mut found = Vec::new();
+        self.collect_unknown("", &mut found);
+        found
+    }
+
+    #[doc(hidden)]
+    fn collect_unknown(&self, path: &str, found: &mut Vec<String>);
+}
+
+fn collect_extra(extra: &Map<String, Value>, path: &str, found: &mut Vec<String>) {
+    for key in extra.keys() {
+        if path.is_empty() {
+            found.push(key.clone());
+        } else {
+            found.push(format!("{path}.{key}"));
+        }
+    }
+}
+
+impl UnknownFields for LiveData {
+    fn collect_unknown(&self, path: &str, found: &mut Vec<String>) {
+        collect_extra(self.extra(), path, found);
+        for device in &self.devices {
+            device.collect_unknown(&format!("{path}devices[{}]", device.zone_name), found);
+        }
+        for sensor in &self.contact_sensors {
+            sensor.collect_unknown(&format!("{path}devices[{}]", sensor.zone_name), found);
+        }
+        for repeater in &self.repeaters {
+            repeater.collect_unknown(&format!("{path}devices[{}]", repeater.zone_name), found);
+        }
+        for device in &self.unparsed {
+            let name = device.zone_name.as_deref().unwrap_or("?");
+            found.push(format!("{path}devices[{name}] ({})", device.error));
+        }
+    }
+}
+
+macro_rules! unknown_fields_from_extra {
+    ($($t:ty),*) => {
+        $(impl UnknownFields for $t {
+            fn collect_unknown(&self, path: &str, found: &mut Vec<String>) {
+                collect_extra(&self.extra, path, found);
+            }
+        })*
+    };
+}
+
+unknown_fields_from_extra!(Device, ContactSensor, Repeater, Profile, EngineersData);
+
+impl<T: UnknownFields> UnknownFields for BTreeMap<String, T> {
+    fn collect_unknown(&self, path: &str, found: &mut Vec<String>) {
+        for (key, value) in self {
+            value.collect_unknown(&format!("{path}[{key}]"), found);
+        }
+    }
+}
+
+impl Client {
+    // like `command_void`, but enforcing `Builder::strict`
+    pub(crate) async fn command_checked<T: DeserializeOwned + UnknownFields>(
+        &self,
+        command: &str,
+    ) -> Result<T> {
+        let value: T = self.command_void(command).await?;
+        self.check_strict(command, &value)?;
+        Ok(value)
+    }
+
+    pub(crate) fn check_strict(&self, command: &str, value: &impl UnknownFields) -> Result<()> {
+        if self.opts.strict {
+            let unknown = value.unknown_fields();
+            if !unknown.is_empty() {
+                bail!(
+                    "strict mode: {} returned unknown fields: {}",
+                    command,
+                    unknown.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+}
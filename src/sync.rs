@@ -0,0 +1,71 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+
+use crate::Client;
+
+/// What [`sync_profiles`] did to one target hub.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    pub url: String,
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    /// Already identical.
+    pub skipped: Vec<String>,
+}
+
+/// Copy the named profiles from `source` to each of the `targets`, matching profiles by name.
+pub async fn sync_profiles(
+    source: &Client,
+    targets: impl IntoIterator<Item = &Client>,
+    names: &[&str],
+) -> Result<Vec<SyncReport>> {
+    let mut available = source.profiles().await.context("reading source profiles")?;
+    let mut wanted = Vec::with_capacity(names.len());
+    for name in names {
+        let profile = available
+            .remove(*name)
+            .ok_or_else(|| anyhow!("source hub has no profile named {:?}", name))?;
+        wanted.push(profile);
+    }
+
+    let mut reports = Vec::new();
+    for target in targets {
+        let mut report = SyncReport {
+            url: target.url.clone(),
+            ..SyncReport::default()
+        };
+        let existing = target
+            .profiles()
+            .await
+            .with_context(|| anyhow!("reading profiles from {}", report.url))?;
+
+        for profile in &wanted {
+            let mut profile = profile.clone();
+            let name = profile.name.clone();
+            match existing.get(&name) {
+                Some(current)
+                    if serde_json::to_value(&current.info)?
+                        == serde_json::to_value(&profile.info)? =>
+                {
+                    report.skipped.push(name);
+                    continue;
+                }
+                // ids are per-hub
+                Some(current) => {
+                    profile.profile_id = current.profile_id;
+                    report.updated.push(name.clone());
+                }
+                None => {
+                    profile.profile_id = 0;
+                    report.created.push(name.clone());
+                }
+            }
+            target
+                .store_profile(&profile)
+                .await
+                .with_context(|| anyhow!("storing {:?} on {}", name, report.url))?;
+        }
+        reports.push(report);
+    }
+    Ok(reports)
+}
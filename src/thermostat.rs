@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, ensure, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{commands, Client, Zone};
+
+/// Per-zone settings, from `GET_ENGINEERS`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct EngineersData {
+    pub device_id: i64,
+    // hours, 0-3
+    pub max_preheat: u8,
+    // degrees, 0-3
+    #[serde(rename = "SWITCHING DIFFERENTIAL")]
+    pub switching_differential: u8,
+    // minutes, 0-15
+    pub output_delay: u8,
+    // degrees, the floor temperature at which heating is cut off
+    pub floor_limit: u8,
+    // heating is turned off while a linked window/door contact is open
+    pub window_switch_open: Option<bool>,
+
+    // see `Device::extra`
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Heating/cooling mode of an HC thermostat.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HcMode {
+    Heating,
+    Cooling,
+    Auto,
+    Vent,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FanSpeed {
+    Off,
+    Low,
+    Medium,
+    High,
+    Auto,
+}
+
+/// A temporary override of a zone's temperature.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Hold {
+    pub temperature: f64,
+    pub remaining: Duration,
+}
+
+// the hub's name for holds we make; it doesn't seem to matter
+const HOLD_ID: &str = "neohub";
+
+impl Client {
+    /// Lock the keypad of the given zones, requiring `pin` (four digits, e.g. "1234") to unlock.
+    pub async fn lock(
+        &self,
+        pin: &str,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let pin = parse_pin(pin)?;
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::LOCK, (pin, zones)).await?;
+        Ok(())
+    }
+
+    /// Lock the keypad of every zone, see [`Client::lock`].
+    pub async fn lock_all(&self, pin: &str) -> Result<()> {
+        let pin = parse_pin(pin)?;
+        let zones = self.all_zone_names().await?;
+        self.command_result(commands::LOCK, (pin, zones)).await?;
+        Ok(())
+    }
+
+    pub async fn unlock(&self, zones: impl IntoIterator<Item = impl Into<Zone>>) -> Result<()> {
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::UNLOCK, zones).await?;
+        Ok(())
+    }
+
+    pub async fn unlock_all(&self) -> Result<()> {
+        let zones = self.all_zone_names().await?;
+        self.command_result(commands::UNLOCK, zones).await?;
+        Ok(())
+    }
+
+    /// Make a zone's thermostat flash its display, to find which device it is.
+    pub async fn identify_device(&self, zone: impl Into<Zone>) -> Result<()> {
+        let zone = self.resolve_zones([zone]).await?.remove(0);
+        self.command_result(commands::IDENTIFY_DEV, zone).await?;
+        Ok(())
+    }
+
+    pub async fn engineers(&self) -> Result<BTreeMap<String, EngineersData>> {
+        self.command_checked(commands::GET_ENGINEERS).await
+    }
+
+    pub async fn zone_engineers(&self, zone: impl Into<Zone>) -> Result<EngineersData> {
+        let zone = self.resolve_zones([zone]).await?.remove(0);
+        self.engineers()
+            .await?
+            .remove(&zone)
+            .ok_or_else(|| anyhow!("zone not found: {:?}", zone))
+    }
+
+    /// Set the maximum optimum start (preheat) time, in hours (0-3).
+    pub async fn set_preheat(
+        &self,
+        hours: u8,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        ensure!(hours <= 3, "preheat must be 0-3 hours, not {}", hours);
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::SET_PREHEAT, (hours, zones))
+            .await?;
+        Ok(())
+    }
+
+    /// Set the switching differential, in degrees (0-3).
+    pub async fn set_switching_differential(
+        &self,
+        degrees: u8,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        ensure!(
+            degrees <= 3,
+            "switching differential must be 0-3 degrees, not {}",
+            degrees
+        );
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::SET_DIFF, (degrees, zones))
+            .await?;
+        Ok(())
+    }
+
+    /// Set the output delay, in minutes (0-15).
+    pub async fn set_output_delay(
+        &self,
+        minutes: u8,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        ensure!(
+            minutes <= 15,
+            "output delay must be 0-15 minutes, not {}",
+            minutes
+        );
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::SET_DELAY, (minutes, zones))
+            .await?;
+        Ok(())
+    }
+
+    /// Set the cooling setpoint of HC thermostats.
+    pub async fn set_cool_temp(
+        &self,
+        temp: f64,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::SET_COOL_TEMP, (temp, zones))
+            .await?;
+        Ok(())
+    }
+
+    /// Switch HC thermostats between heating, cooling, etc.
+    pub async fn set_hc_mode(
+        &self,
+        mode: HcMode,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        ensure!(mode != HcMode::Unknown, "can't set an unknown hc mode");
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::SET_HC_MODE, (mode, zones))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_fan_speed(
+        &self,
+        speed: FanSpeed,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::SET_FAN_SPEED, (speed, zones))
+            .await?;
+        Ok(())
+    }
+
+    /// Set the target temperature of the given zones, until their next profile change.
+    pub async fn set_temperature(
+        &self,
+        temp: f64,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::SET_TEMP, (temp, zones))
+            .await?;
+        Ok(())
+    }
+
+    /// Hold the zones at `temp` for `duration` (whole minutes, up to 99 hours), ignoring
+    /// their profile.
+    pub async fn hold(
+        &self,
+        temp: f64,
+        duration: Duration,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let minutes = duration.as_secs() / 60;
+        ensure!(
+            minutes > 0 && minutes < 100 * 60,
+            "hold must be between a minute and 99 hours, not {:?}",
+            duration
+        );
+        let hold = json!({
+            "temp": temp,
+            "hours": minutes / 60,
+            "minutes": minutes % 60,
+            "id": HOLD_ID,
+        });
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::HOLD, (hold, zones)).await?;
+        Ok(())
+    }
+
+    /// Cancel every hold, on every zone.
+    pub async fn cancel_hold_all(&self) -> Result<()> {
+        self.command_result(commands::CANCEL_HOLD_ALL, 0).await?;
+        Ok(())
+    }
+
+    /// The zone's active hold, if any, from live data.
+    pub async fn active_hold(&self, zone: impl Into<Zone>) -> Result<Option<Hold>> {
+        let zone = self.resolve_zones([zone]).await?.remove(0);
+        let live_data = self.live_data().await?;
+        let device = live_data
+            .devices
+            .iter()
+            .find(|device| device.zone_name == zone)
+            .ok_or_else(|| anyhow!("zone not found: {:?}", zone))?;
+        device.hold()
+    }
+
+    /// Switch timeclock zones (e.g. hot water, a towel rail) on for `duration` (whole
+    /// minutes), ignoring their timer profile.
+    pub async fn boost(
+        &self,
+        duration: Duration,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let minutes = duration.as_secs() / 60;
+        ensure!(
+            minutes > 0 && minutes < 100 * 60,
+            "boost must be between a minute and 99 hours, not {:?}",
+            duration
+        );
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::TIMER_HOLD_ON, (minutes, zones))
+            .await?;
+        Ok(())
+    }
+
+    /// Cancel a [`Client::boost`], returning the zones to their timer profile.
+    pub async fn cancel_boost(
+        &self,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::TIMER_HOLD_OFF, (0, zones))
+            .await?;
+        Ok(())
+    }
+
+    /// Put zones into standby, only heating to their frost protection temperature.
+    pub async fn standby(&self, zones: impl IntoIterator<Item = impl Into<Zone>>) -> Result<()> {
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::FROST_ON, zones).await?;
+        Ok(())
+    }
+
+    /// Take zones out of [`Client::standby`].
+    pub async fn resume(&self, zones: impl IntoIterator<Item = impl Into<Zone>>) -> Result<()> {
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::FROST_OFF, zones).await?;
+        Ok(())
+    }
+
+    /// Put every heating zone (not timeclocks, e.g. hot water, or plugs) into standby, in one
+    /// command; returns the zones.
+    pub async fn all_standby(&self) -> Result<Vec<String>> {
+        let zones = self.heating_zone_names().await?;
+        self.standby(&zones).await?;
+        Ok(zones)
+    }
+
+    /// Take every heating zone out of standby, see [`Client::all_standby`].
+    pub async fn all_resume(&self) -> Result<Vec<String>> {
+        let zones = self.heating_zone_names().await?;
+        self.resume(&zones).await?;
+        Ok(zones)
+    }
+
+    async fn heating_zone_names(&self) -> Result<Vec<String>> {
+        Ok(self
+            .live_data()
+            .await?
+            .devices
+            .into_iter()
+            .filter(|device| device.device_type().is_thermostat())
+            .map(|device| device.zone_name)
+            .collect())
+    }
+
+    /// Set the floor limit temperature, for stats with a floor sensor, in degrees (15-45).
+    pub async fn set_floor_limit(
+        &self,
+        degrees: u8,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        ensure!(
+            (15..=45).contains(&degrees),
+            "floor limit must be 15-45 degrees, not {}",
+            degrees
+        );
+        let zones = self.resolve_zones(zones).await?;
+        self.command_result(commands::SET_FLOOR, (degrees, zones))
+            .await?;
+        Ok(())
+    }
+}
+
+// the hub wants the pin as an array of digits
+fn parse_pin(pin: &str) -> Result<[u8; 4]> {
+    ensure!(
+        pin.len() == 4 && pin.bytes().all(|b| b.is_ascii_digit()),
+        "pin must be four digits: {:?}",
+        pin
+    );
+    let mut digits = [0; 4];
+    for (digit, b) in digits.iter_mut().zip(pin.bytes()) {
+        *digit = b - b'0';
+    }
+    Ok(digits)
+}
+
+// "H:MM", as in live data's HOLD_TIME
+pub(crate) fn parse_hours_minutes(value: &str) -> Result<Duration> {
+    let (hours, minutes) = value
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| anyhow!("expected H:MM, not {:?}", value))?;
+    let hours: u64 = hours.parse()?;
+    let minutes: u64 = minutes.parse()?;
+    Ok(Duration::from_secs((hours * 60 + minutes) * 60))
+}
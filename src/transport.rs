@@ -0,0 +1,115 @@
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::future::BoxFuture;
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::WsStream;
+
+// see `Builder::transport`
+pub(crate) type Connect = dyn Fn() -> BoxFuture<'static, Result<Box<dyn Transport>>> + Send + Sync;
+
+/// Carries the hub's text frames (see [`crate::protocol`]) both ways; a websocket, unless
+/// [`crate::Builder::transport`] says otherwise, e.g. for tests.
+///
+/// One task receives, while any number of others send.
+pub trait Transport: Send + Sync + 'static {
+    /// Send one frame.
+    fn send(&self, frame: String) -> BoxFuture<'_, Result<()>>;
+
+    /// The next frame, or `None` once the hub has closed the connection.
+    fn recv(&self) -> BoxFuture<'_, Result<Option<String>>>;
+
+    /// Check the hub is still there, finishing when it answers; see [`crate::Client::ping`].
+    /// By default, there's nothing to check.
+    fn ping(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// By default, there's nothing to close.
+    fn close(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// The hub's own transport.
+pub(crate) struct WebSocket {
+    sink: tokio::sync::Mutex<SplitSink<WsStream, Message>>,
+    stream: tokio::sync::Mutex<SplitStream<WsStream>>,
+    // pings waiting for a pong
+    pongs: Mutex<Vec<oneshot::Sender<()>>>,
+}
+
+impl WebSocket {
+    pub(crate) fn new(ws: WsStream) -> Self {
+        let (sink, stream) = ws.split();
+        WebSocket {
+            sink: tokio::sync::Mutex::new(sink),
+            stream: tokio::sync::Mutex::new(stream),
+            pongs: Mutex::default(),
+        }
+    }
+
+    async fn write(&self, msg: Message) -> Result<()> {
+        let mut sink = self.sink.lock().await;
+        sink.feed(msg).await?;
+        sink.flush().await?;
+        Ok(())
+    }
+
+    fn wake_pongs(&self) {
+        for tx in self.pongs.lock().expect("pongs poisoned").drain(..) {
+            let _ = tx.send(());
+        }
+    }
+}
+
+impl Transport for WebSocket {
+    fn send(&self, frame: String) -> BoxFuture<'_, Result<()>> {
+        Box::pin(self.write(Message::Text(frame)))
+    }
+
+    fn recv(&self) -> BoxFuture<'_, Result<Option<String>>> {
+        Box::pin(async move {
+            let mut stream = self.stream.lock().await;
+            let closed = loop {
+                let Some(msg) = stream.next().await else {
+                    break Err(anyhow!("connection ended"));
+                };
+                match msg.context("websocket error") {
+                    Ok(Message::Text(text)) => return Ok(Some(text)),
+                    Ok(Message::Binary(data)) => {
+                        return Ok(Some(String::from_utf8_lossy(&data).into_owned()))
+                    }
+                    Ok(Message::Close(_)) => break Ok(None),
+                    Ok(Message::Pong(_)) => self.wake_pongs(),
+                    Ok(_) => continue,
+                    Err(e) => break Err(e),
+                }
+            };
+            // no pongs are coming
+            self.pongs.lock().expect("pongs poisoned").clear();
+            closed
+        })
+    }
+
+    fn ping(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let (tx, rx) = oneshot::channel();
+            self.pongs.lock().expect("pongs poisoned").push(tx);
+            self.write(Message::Ping(Vec::new())).await?;
+            rx.await
+                .map_err(|_| anyhow!("connection closed before a pong was received"))
+        })
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.sink.lock().await.close().await?;
+            Ok(())
+        })
+    }
+}
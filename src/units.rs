@@ -0,0 +1,94 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{commands, Client, Zone};
+
+/// How the hub shows (and expects) temperatures, from `GET_SYSTEM`'s `CORF`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    /// As in `GET_SYSTEM`'s `CORF`, `"C"` or `"F"`.
+    pub fn from_corf(corf: &str) -> Option<Self> {
+        match corf {
+            "C" => Some(TemperatureUnit::Celsius),
+            "F" => Some(TemperatureUnit::Fahrenheit),
+            _ => None,
+        }
+    }
+
+    pub fn corf(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "C",
+            TemperatureUnit::Fahrenheit => "F",
+        }
+    }
+
+    /// Convert `temp` from this unit to `to`.
+    pub fn convert(self, temp: f64, to: TemperatureUnit) -> f64 {
+        match (self, to) {
+            (TemperatureUnit::Celsius, TemperatureUnit::Fahrenheit) => temp * 9.0 / 5.0 + 32.0,
+            (TemperatureUnit::Fahrenheit, TemperatureUnit::Celsius) => (temp - 32.0) * 5.0 / 9.0,
+            _ => temp,
+        }
+    }
+}
+
+impl Client {
+    /// The hub's temperature unit, detected on first use, unless set with
+    /// [`crate::Builder::temperature_unit`].
+    ///
+    /// Live data, profiles and commands all use this unit.
+    pub async fn temperature_unit(&self) -> Result<TemperatureUnit> {
+        if let Some(unit) = *self.temperature_unit.lock().expect("client poisoned") {
+            return Ok(unit);
+        }
+        let system: Value = self.command_void(commands::GET_SYSTEM).await?;
+        let unit = system
+            .get("CORF")
+            .and_then(Value::as_str)
+            .and_then(TemperatureUnit::from_corf)
+            .ok_or_else(|| anyhow!("GET_SYSTEM had no CORF: {}", system))?;
+        *self.temperature_unit.lock().expect("client poisoned") = Some(unit);
+        Ok(unit)
+    }
+
+    /// Convert a temperature in `unit` to the hub's unit.
+    pub async fn to_hub_unit(&self, temp: f64, unit: TemperatureUnit) -> Result<f64> {
+        Ok(unit.convert(temp, self.temperature_unit().await?))
+    }
+
+    /// Convert a temperature from the hub (e.g. from live data) to `unit`.
+    pub async fn from_hub_unit(&self, temp: f64, unit: TemperatureUnit) -> Result<f64> {
+        Ok(self.temperature_unit().await?.convert(temp, unit))
+    }
+
+    /// Like [`Client::set_temperature`], in celsius, whatever the hub's unit.
+    pub async fn set_temp_c(
+        &self,
+        temp: f64,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let temp = self.to_hub_unit(temp, TemperatureUnit::Celsius).await?;
+        self.set_temperature(round_half(temp), zones).await
+    }
+
+    /// Like [`Client::set_temperature`], in fahrenheit, whatever the hub's unit.
+    pub async fn set_temp_f(
+        &self,
+        temp: f64,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<()> {
+        let temp = self.to_hub_unit(temp, TemperatureUnit::Fahrenheit).await?;
+        self.set_temperature(round_half(temp), zones).await
+    }
+}
+
+// thermostats take whole or half degrees
+fn round_half(temp: f64) -> f64 {
+    (temp * 2.0).round() / 2.0
+}
@@ -0,0 +1,231 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::profile_file::parse_time;
+use crate::{commands, Client, Day, Event, Profile, ScheduleFormat, TemperatureUnit};
+
+/// Why [`Profile::validate`] thinks the hub won't accept a profile, or won't use it as
+/// written; find these with `anyhow::Error::downcast_ref`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidProfile {
+    pub name: String,
+    pub problems: Vec<ProfileProblem>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfileProblem {
+    /// Not "HH:MM".
+    BadTime {
+        day: Day,
+        event: Event,
+        time: String,
+    },
+    /// Events must be in order through the day.
+    OutOfOrder {
+        day: Day,
+        event: Event,
+        time: String,
+        previous: String,
+    },
+    OutOfRange {
+        day: Day,
+        event: Event,
+        temperature: f64,
+        min: f64,
+        max: f64,
+    },
+    /// The hub only accepts whole multiples of `step`.
+    BadStep {
+        day: Day,
+        event: Event,
+        temperature: f64,
+        step: f64,
+    },
+    /// The hub's format ignores this day, using `uses` instead, but they differ.
+    Ignored { day: Day, uses: Day },
+    /// The hub doesn't run profiles at all.
+    NonProgrammable,
+}
+
+impl fmt::Display for ProfileProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileProblem::BadTime { day, event, time } => {
+                write!(f, "{:?} {:?} time {:?} isn't HH:MM", day, event, time)
+            }
+            ProfileProblem::OutOfOrder {
+                day,
+                event,
+                time,
+                previous,
+            } => write!(
+                f,
+                "{:?} {:?} at {} is not after the previous event, at {}",
+                day, event, time, previous
+            ),
+            ProfileProblem::OutOfRange {
+                day,
+                event,
+                temperature,
+                min,
+                max,
+            } => write!(
+                f,
+                "{:?} {:?} temperature {} is outside {}-{}",
+                day, event, temperature, min, max
+            ),
+            ProfileProblem::BadStep {
+                day,
+                event,
+                temperature,
+                step,
+            } => write!(
+                f,
+                "{:?} {:?} temperature {} isn't a multiple of {}",
+                day, event, temperature, step
+            ),
+            ProfileProblem::Ignored { day, uses } => write!(
+                f,
+                "{:?} differs from {:?}, but the hub uses {:?}'s schedule for it",
+                day, uses, uses
+            ),
+            ProfileProblem::NonProgrammable => write!(f, "the hub is non-programmable"),
+        }
+    }
+}
+
+impl fmt::Display for InvalidProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid profile {:?}: ", self.name)?;
+        for (i, problem) in self.problems.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvalidProfile {}
+
+// the range and step the hub's thermostats accept
+fn limits(unit: TemperatureUnit) -> (f64, f64, f64) {
+    match unit {
+        TemperatureUnit::Celsius => (5.0, 35.0, 0.5),
+        TemperatureUnit::Fahrenheit => (41.0, 95.0, 1.0),
+    }
+}
+
+impl Profile {
+    /// Check the profile is something the hub will accept, and use as written, for a hub
+    /// with `format` and `unit` (see [`Client::validate_profile`]); reporting every
+    /// problem, not just the first.
+    pub fn validate(
+        &self,
+        format: ScheduleFormat,
+        unit: TemperatureUnit,
+    ) -> std::result::Result<(), InvalidProfile> {
+        let mut problems = self.time_problems();
+        let (min, max, step) = limits(unit);
+        for day in Day::ALL {
+            for event in Event::ALL {
+                let temperature = self.info.day(day).event(event).temperature;
+                if !(min..=max).contains(&temperature) {
+                    problems.push(ProfileProblem::OutOfRange {
+                        day,
+                        event,
+                        temperature,
+                        min,
+                        max,
+                    });
+                } else if (temperature / step).fract() != 0.0 {
+                    problems.push(ProfileProblem::BadStep {
+                        day,
+                        event,
+                        temperature,
+                        step,
+                    });
+                }
+            }
+        }
+        if format == ScheduleFormat::NonProgrammable {
+            problems.push(ProfileProblem::NonProgrammable);
+        }
+        for day in Day::ALL {
+            match format.profile_day(day) {
+                Some(uses) if uses != day && self.info.day(day) != self.info.day(uses) => {
+                    problems.push(ProfileProblem::Ignored { day, uses });
+                }
+                _ => {}
+            }
+        }
+        self.result(problems)
+    }
+
+    // every comfort level has a valid time, and they're in order through the day
+    pub(crate) fn check_times(&self) -> std::result::Result<(), InvalidProfile> {
+        self.result(self.time_problems())
+    }
+
+    fn time_problems(&self) -> Vec<ProfileProblem> {
+        let mut problems = Vec::new();
+        for day in Day::ALL {
+            let mut previous: Option<(u16, &str)> = None;
+            for event in Event::ALL {
+                let time = &self.info.day(day).event(event).time;
+                let Ok(minutes) = parse_time(time) else {
+                    problems.push(ProfileProblem::BadTime {
+                        day,
+                        event,
+                        time: time.clone(),
+                    });
+                    continue;
+                };
+                if let Some((before, before_time)) = previous {
+                    if minutes <= before {
+                        problems.push(ProfileProblem::OutOfOrder {
+                            day,
+                            event,
+                            time: time.clone(),
+                            previous: before_time.to_string(),
+                        });
+                    }
+                }
+                previous = Some((minutes, time));
+            }
+        }
+        problems
+    }
+
+    fn result(&self, problems: Vec<ProfileProblem>) -> std::result::Result<(), InvalidProfile> {
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidProfile {
+                name: self.name.clone(),
+                problems,
+            })
+        }
+    }
+}
+
+impl Client {
+    /// How the hub applies profiles to days, from `GET_SYSTEM`'s `FORMAT`.
+    pub async fn schedule_format(&self) -> Result<ScheduleFormat> {
+        let system: Value = self.command_void(commands::GET_SYSTEM).await?;
+        let format = system.get("FORMAT").cloned().unwrap_or(Value::Null);
+        serde_json::from_value(format).with_context(|| "reading FORMAT from GET_SYSTEM")
+    }
+
+    /// [`Profile::validate`], for this hub's format and temperature unit; e.g. before
+    /// [`Client::store_profile`], which the hub fails without saying why.
+    pub async fn validate_profile(&self, profile: &Profile) -> Result<()> {
+        let format = self.schedule_format().await?;
+        let unit = self.temperature_unit().await?;
+        profile.validate(format, unit)?;
+        Ok(())
+    }
+}
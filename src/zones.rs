@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{commands, Client};
+
+/// A zone, by name or by device id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Zone {
+    Name(String),
+    Id(i64),
+}
+
+impl From<&str> for Zone {
+    fn from(name: &str) -> Self {
+        Zone::Name(name.to_string())
+    }
+}
+
+impl From<&String> for Zone {
+    fn from(name: &String) -> Self {
+        Zone::Name(name.clone())
+    }
+}
+
+impl From<String> for Zone {
+    fn from(name: String) -> Self {
+        Zone::Name(name)
+    }
+}
+
+impl From<i64> for Zone {
+    fn from(id: i64) -> Self {
+        Zone::Id(id)
+    }
+}
+
+/// The zones known to the hub, from `GET_ZONES`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(from = "BTreeMap<String, i64>", into = "BTreeMap<String, i64>")]
+pub struct Zones {
+    by_name: BTreeMap<String, i64>,
+    by_id: BTreeMap<i64, String>,
+}
+
+impl From<BTreeMap<String, i64>> for Zones {
+    fn from(by_name: BTreeMap<String, i64>) -> Self {
+        let by_id = by_name
+            .iter()
+            .map(|(name, id)| (*id, name.clone()))
+            .collect();
+        Zones { by_name, by_id }
+    }
+}
+
+impl From<Zones> for BTreeMap<String, i64> {
+    fn from(zones: Zones) -> Self {
+        zones.by_name
+    }
+}
+
+impl Zones {
+    pub fn id(&self, name: &str) -> Option<i64> {
+        self.by_name.get(name).copied()
+    }
+
+    pub fn name(&self, id: i64) -> Option<&str> {
+        self.by_id.get(&id).map(String::as_str)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.by_name.keys().map(String::as_str)
+    }
+
+    /// (name, id) pairs, in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, i64)> {
+        self.by_name.iter().map(|(name, id)| (name.as_str(), *id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    /// The name of a zone, which is what the hub wants in commands.
+    pub fn resolve(&self, zone: &Zone) -> Result<String> {
+        match zone {
+            Zone::Name(name) => Ok(name.clone()),
+            Zone::Id(id) => self
+                .name(*id)
+                .map(str::to_owned)
+                .ok_or_else(|| anyhow!("no zone with id {}", id)),
+        }
+    }
+}
+
+impl Client {
+    pub async fn zones_map(&self) -> Result<Zones> {
+        self.command_void(commands::GET_ZONES).await
+    }
+
+    // zone names, only asking the hub if we were given any ids
+    pub(crate) async fn resolve_zones(
+        &self,
+        zones: impl IntoIterator<Item = impl Into<Zone>>,
+    ) -> Result<Vec<String>> {
+        let zones: Vec<Zone> = zones.into_iter().map(Into::into).collect();
+        if zones.iter().all(|zone| matches!(zone, Zone::Name(_))) {
+            return Ok(zones
+                .into_iter()
+                .map(|zone| match zone {
+                    Zone::Name(name) => name,
+                    Zone::Id(_) => unreachable!("checked above"),
+                })
+                .collect());
+        }
+        let map = self.zones_map().await?;
+        zones.iter().map(|zone| map.resolve(zone)).collect()
+    }
+
+    pub(crate) async fn all_zone_names(&self) -> Result<Vec<String>> {
+        Ok(self.zones_map().await?.names().map(str::to_owned).collect())
+    }
+}
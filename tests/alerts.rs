@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use neohub::alerts::{AlertState, Alerts, Rule};
+use neohub::LiveData;
+use tokio::time::Instant;
+
+fn office_at(temp: &str) -> LiveData {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"][0]["ACTUAL_TEMP"] = temp.into();
+    serde_json::from_value(live_data).unwrap()
+}
+
+#[test]
+fn below_with_duration_and_hysteresis() {
+    let minutes = |m: u64| Duration::from_secs(m * 60);
+    let mut alerts = Alerts::new().rule(Rule::below("Office", 16.0, minutes(10)));
+    let start = Instant::now();
+
+    assert!(alerts.observe(&office_at("15.5"), start).is_empty());
+    assert!(alerts
+        .observe(&office_at("15.5"), start + minutes(5))
+        .is_empty());
+    let raised = alerts.observe(&office_at("15.4"), start + minutes(10));
+    assert_eq!(raised.len(), 1);
+    assert_eq!(raised[0].state, AlertState::Raised);
+    assert_eq!(raised[0].temperature, Some(15.4));
+
+    // back over the threshold, but not by the hysteresis
+    assert!(alerts
+        .observe(&office_at("16.2"), start + minutes(11))
+        .is_empty());
+    let cleared = alerts.observe(&office_at("16.6"), start + minutes(12));
+    assert_eq!(cleared[0].state, AlertState::Cleared);
+}
+
+#[test]
+fn offline_sensor() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({
+            "ZONE_NAME": "Back Door",
+            "DEVICE_ID": 7,
+            "WINDOW_OPEN": false,
+            "LOW_BATTERY": false,
+            "OFFLINE": true,
+        }));
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+
+    let mut alerts = Alerts::new().rule(Rule::offline(Duration::ZERO));
+    let raised = alerts.observe(&live_data, Instant::now());
+    assert_eq!(raised.len(), 1);
+    assert_eq!(raised[0].zone, "Back Door");
+    assert_eq!(raised[0].temperature, None);
+
+    // the sensor's removed
+    let cleared = alerts.observe(&office_at("20.0"), Instant::now());
+    assert_eq!(cleared.len(), 1);
+    assert_eq!(cleared[0].zone, "Back Door");
+    assert_eq!(cleared[0].state, AlertState::Cleared);
+    // and it's forgotten, so comes back as new
+    let raised = alerts.observe(&live_data, Instant::now());
+    assert_eq!(raised[0].state, AlertState::Raised);
+}
@@ -0,0 +1,106 @@
+use neohub::{Backup, EngineersData, Profile};
+use serde_json::json;
+
+fn profile(id: u16, name: &str) -> Profile {
+    let mut profile: Profile = serde_json::from_str(include_str!("profile-1.json")).unwrap();
+    profile.profile_id = id;
+    profile.name = name.to_string();
+    profile
+}
+
+fn engineers(max_preheat: u8) -> EngineersData {
+    serde_json::from_value(json!({
+        "DEVICE_ID": 1,
+        "MAX_PREHEAT": max_preheat,
+        "SWITCHING DIFFERENTIAL": 1,
+        "OUTPUT_DELAY": 0,
+        "FLOOR_LIMIT": 28,
+    }))
+    .unwrap()
+}
+
+fn hub() -> Backup {
+    Backup {
+        firmware_version: Some("2134".to_string()),
+        zones: serde_json::from_value(json!({"Office": 1, "Hall": 2})).unwrap(),
+        profiles: [
+            ("Winter".to_string(), profile(1, "Winter")),
+            ("Summer".to_string(), profile(2, "Summer")),
+        ]
+        .into(),
+        comfort_levels: [("Office".to_string(), json!({"wake": ["06:30", 21]}))].into(),
+        engineers: [("Office".to_string(), engineers(2))].into(),
+        system: json!({"CORF": "C", "FORMAT": "7DAY", "TIME_ZONE": 0.0, "NTP_ON": "Running"}),
+    }
+}
+
+#[test]
+fn unchanged() {
+    let report = hub().plan_restore(&hub()).unwrap();
+    assert!(report.changes.is_empty(), "{report:?}");
+    assert!(report.warnings.is_empty(), "{report:?}");
+}
+
+#[test]
+fn plan_restore() {
+    let current = hub();
+
+    let mut wanted = hub();
+    wanted.zones = serde_json::from_value(json!({"Office": 1, "Loft": 3})).unwrap();
+    wanted.profiles.clear();
+    // Winter was 7 when the backup was taken, and is 1 now
+    let mut winter = profile(7, "Winter");
+    winter.info.monday.wake.time = "07:00".to_string();
+    wanted.profiles.insert("Winter".to_string(), winter);
+    // Autumn was 2, which is now Summer's
+    wanted
+        .profiles
+        .insert("Autumn".to_string(), profile(2, "Autumn"));
+    wanted.comfort_levels = [("Office".to_string(), json!({"wake": ["07:00", 20]}))].into();
+    wanted.engineers = [
+        ("Office".to_string(), engineers(3)),
+        ("Hall".to_string(), engineers(1)),
+    ]
+    .into();
+    // "5DAY", as some firmware reports it
+    wanted.system = json!({"CORF": "F", "FORMAT": 2, "TIME_ZONE": 1.0, "NTP_ON": "Off"});
+
+    let report = current.plan_restore(&wanted).unwrap();
+    assert_eq!(
+        report.warnings,
+        [
+            "zone \"Loft\" is missing, and must be paired again",
+            "comfort levels of \"Office\" differ, and must be set by hand",
+        ]
+    );
+    let changes: Vec<_> = report
+        .changes
+        .iter()
+        .map(|change| (change.command, change.args.clone()))
+        .collect();
+    assert_eq!(
+        changes[..2],
+        [
+            // a new profile, not Summer's id
+            (
+                "STORE_PROFILE2",
+                json!({"name": "Autumn", "info": profile(2, "Autumn").info})
+            ),
+            // the hub's id for Winter, not the backup's
+            (
+                "STORE_PROFILE2",
+                json!({"ID": 1, "name": "Winter", "info": wanted.profiles["Winter"].info})
+            ),
+        ]
+    );
+    assert_eq!(
+        changes[2..],
+        [
+            // Hall has no engineers data on the hub, so it's left alone
+            ("SET_PREHEAT", json!([3, ["Office"]])),
+            ("SET_TEMP_FORMAT", json!("F")),
+            ("TIME_ZONE", json!(1.0)),
+            ("SET_FORMAT", json!("5DAY")),
+        ]
+    );
+}
@@ -0,0 +1,62 @@
+use neohub::Client;
+
+#[test]
+fn build_host() {
+    let client = Client::build_host("192.168.13.37", None, "token")
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(client.url(), "wss://192.168.13.37:4243");
+    let client = Client::build_host("neohub.local", 1234, "token")
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(client.url(), "wss://neohub.local:1234");
+
+    for (host, url) in [
+        ("fd00::1", "wss://[fd00::1]:4243"),
+        ("[fd00::1]", "wss://[fd00::1]:4243"),
+        ("2001:db8::4:1", "wss://[2001:db8::4:1]:4243"),
+    ] {
+        let client = Client::build_host(host, None, "token")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(client.url(), url);
+    }
+
+    for bad in [
+        "",
+        "wss://192.168.13.37:4243",
+        "neohub.local/",
+        "neo hub",
+        "neo_hub:1",
+    ] {
+        assert!(
+            Client::build_host(bad, None, "token").is_err(),
+            "{bad:?} accepted"
+        );
+    }
+}
+
+// the only test in this file which touches the environment
+#[test]
+fn from_env() {
+    std::env::remove_var("NEOHUB_URL");
+    std::env::set_var("NEOHUB_HOST", "fd00::1");
+    std::env::set_var("NEOHUB_PORT", "1234");
+    std::env::set_var("NEOHUB_TOKEN", "token");
+    std::env::set_var("NEOHUB_TIMEOUT_SECS", "2.5");
+    assert_eq!(Client::from_env().unwrap().url(), "wss://[fd00::1]:1234");
+
+    std::env::set_var("NEOHUB_TIMEOUT_SECS", "soon");
+    assert!(Client::from_env().is_err());
+    std::env::remove_var("NEOHUB_TIMEOUT_SECS");
+
+    std::env::remove_var("NEOHUB_HOST");
+    std::env::set_var("NEOHUB_URL", "wss://hub:4243");
+    let Err(err) = Client::from_env() else {
+        panic!("NEOHUB_PORT with NEOHUB_URL was accepted");
+    };
+    assert!(err.to_string().contains("NEOHUB_PORT"), "{err}");
+}
@@ -10,3 +10,371 @@ fn live_data() {
         6
     );
 }
+
+#[test]
+fn no_floor_sensor() {
+    let live_data: LiveData = serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    assert!(live_data
+        .devices
+        .iter()
+        .all(|device| device.floor_temperature().is_none()));
+}
+
+#[test]
+fn batteries() {
+    let live_data: LiveData = serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    assert!(live_data.low_battery_devices().is_empty());
+    assert_eq!(
+        live_data.devices[0].power_source(),
+        neohub::PowerSource::Unknown
+    );
+}
+
+#[test]
+fn offline() {
+    let live_data: LiveData = serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    assert!(live_data.offline_devices().is_empty());
+    assert!(live_data
+        .stale_devices(std::time::Duration::from_secs(60))
+        .is_empty());
+}
+
+#[test]
+fn no_humidity_sensor() {
+    let live_data: LiveData = serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    assert!(live_data
+        .devices
+        .iter()
+        .all(|device| device.humidity().is_none()));
+}
+
+#[test]
+fn hc_mode() {
+    let live_data: LiveData = serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    assert_eq!(live_data.devices[0].hc_mode, neohub::HcMode::Heating);
+    assert_eq!(
+        serde_json::from_str::<neohub::HcMode>("\"SOMETHING_NEW\"").unwrap(),
+        neohub::HcMode::Unknown
+    );
+}
+
+#[test]
+fn contact_sensor() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({
+            "ZONE_NAME": "Back Door",
+            "DEVICE_ID": 7,
+            "WINDOW_OPEN": true,
+            "LOW_BATTERY": true,
+            "OFFLINE": false,
+        }));
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+    assert_eq!(live_data.devices.len(), 6);
+    assert_eq!(live_data.open_windows()[0].zone_name, "Back Door");
+    let low_battery = live_data.low_battery_devices();
+    assert_eq!(low_battery.len(), 1);
+    assert_eq!(low_battery[0].zone_name(), "Back Door");
+
+    let round_trip: LiveData =
+        serde_json::from_value(serde_json::to_value(&live_data).unwrap()).unwrap();
+    assert_eq!(round_trip.contact_sensors.len(), 1);
+}
+
+#[test]
+fn repeater() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({
+            "ZONE_NAME": "repeaternode54473",
+            "OFFLINE": true,
+        }));
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+    assert_eq!(live_data.devices.len(), 6);
+    assert_eq!(live_data.repeaters.len(), 1);
+    assert_eq!(live_data.offline_repeaters().len(), 1);
+    assert!(matches!(
+        live_data.offline_devices()[..],
+        [neohub::DeviceRef::Repeater(repeater)] if repeater.zone_name == "repeaternode54473"
+    ));
+}
+
+#[test]
+fn zones() {
+    let zones: neohub::Zones = serde_json::from_str(r#"{"Office": 1, "Hot Water": 5}"#).unwrap();
+    assert_eq!(zones.id("Office"), Some(1));
+    assert_eq!(zones.name(5), Some("Hot Water"));
+    assert_eq!(zones.resolve(&neohub::Zone::Id(5)).unwrap(), "Hot Water");
+    assert!(zones.resolve(&neohub::Zone::Id(6)).is_err());
+}
+
+#[test]
+fn unknown_fields() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["NEW_THING"] = true.into();
+    live_data["devices"][4]["NEW_DEVICE_THING"] = 1.into();
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+    assert!(live_data.extra().contains_key("NEW_THING"));
+    assert!(live_data.devices[4].extra.contains_key("NEW_DEVICE_THING"));
+
+    use neohub::UnknownFields;
+    assert_eq!(
+        live_data.unknown_fields(),
+        ["NEW_THING", "devices[Hot Water].NEW_DEVICE_THING"]
+    );
+}
+
+#[test]
+fn unparsed_device() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({ "ZONE_NAME": "Exotic", "SOMETHING": [] }));
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+    assert_eq!(live_data.devices.len(), 6);
+    assert_eq!(live_data.unparsed.len(), 1);
+    assert_eq!(live_data.unparsed[0].zone_name.as_deref(), Some("Exotic"));
+}
+
+#[test]
+fn broken_thermostat() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"][0]
+        .as_object_mut()
+        .unwrap()
+        .remove("HOLD_TEMP");
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+    assert_eq!(live_data.devices.len(), 5);
+    assert!(live_data.contact_sensors.is_empty());
+    assert_eq!(live_data.unparsed[0].zone_name.as_deref(), Some("Office"));
+}
+
+#[test]
+fn faults() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"][2]["ERROR_CODE"] = "E2".into();
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+    let faulty = live_data.faulty_devices();
+    assert_eq!(faulty.len(), 1);
+    assert_eq!(faulty[0].0.zone_name, "Middle Floor");
+    assert_eq!(faulty[0].1, neohub::DeviceFault::FloorSensor);
+}
+
+#[test]
+fn hold() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"][0]["HOLD_ON"] = true.into();
+    live_data["devices"][0]["HOLD_TIME"] = "1:30".into();
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+
+    let hold = live_data.devices[0].hold().unwrap().unwrap();
+    assert_eq!(hold.temperature, 23.0);
+    assert_eq!(hold.remaining, std::time::Duration::from_secs(90 * 60));
+    assert!(live_data.devices[1].hold().unwrap().is_none());
+}
+
+#[test]
+fn round_trip() {
+    let live_data: LiveData = serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    let again: LiveData =
+        serde_json::from_value(serde_json::to_value(&live_data).unwrap()).unwrap();
+    assert_eq!(live_data, again);
+    assert_eq!(live_data.clone(), live_data);
+}
+
+#[test]
+fn network_health() {
+    let mut value: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    value["devices"][1]["OFFLINE"] = true.into();
+    value["devices"][1]["TIMESTAMP"] = 1_600_000_000.into();
+    let devices = value["devices"].as_array_mut().unwrap();
+    devices.push(serde_json::json!({
+        "ZONE_NAME": "Back Door",
+        "DEVICE_ID": 7,
+        "WINDOW_OPEN": false,
+        "LOW_BATTERY": false,
+        "OFFLINE": false,
+        "TIMESTAMP": 1_663_101_000,
+    }));
+    devices.push(serde_json::json!({
+        "ZONE_NAME": "repeaternode54473",
+        "OFFLINE": false,
+        "TIMESTAMP": 1_663_000_000,
+    }));
+    let live_data: LiveData = serde_json::from_value(value).unwrap();
+    let health = neohub::NetworkHealth::from_live_data(&live_data);
+    assert_eq!(health.links.len(), 8);
+    let offline = health.offline();
+    assert_eq!(offline.len(), 1);
+    assert_eq!(offline[0].zone_name, "Top Floor");
+    assert_eq!(offline[0].last_seen, Some(1_600_000_000));
+    assert_eq!(health.links[0].last_seen, None);
+    assert_eq!(health.links[6].last_seen, Some(1_663_101_000));
+    assert_eq!(health.links[7].last_seen, Some(1_663_000_000));
+
+    // the repeater last reported over a day before the hub's clock
+    let stale = live_data.stale_devices(std::time::Duration::from_secs(3600));
+    let stale: Vec<_> = stale.iter().map(|device| device.zone_name()).collect();
+    assert_eq!(stale, ["Top Floor", "repeaternode54473"]);
+}
+
+#[test]
+fn hub_time_zone() {
+    use neohub::TimeZone;
+
+    let live_data: LiveData = serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    // british summer time: the stats show 21:43 at 20:43 UTC
+    let summer = TimeZone::observed("tuesday", "21:43", live_data.hub_time()).unwrap();
+    assert_eq!(summer.offset_seconds, 3600);
+    assert_eq!(
+        live_data.hub_datetime(summer).to_string(),
+        "2022-09-13T21:43:03+01:00"
+    );
+    // GMT: 2022-12-13 20:43:03 UTC, a few minutes after the stat last reported
+    let winter = TimeZone::observed("tuesday", "20:39", 1_670_964_183).unwrap();
+    assert_eq!(winter, TimeZone::UTC);
+    assert_eq!(winter.at(1_670_964_183).to_string(), "2022-12-13T20:43:03Z");
+    // across midnight
+    let ahead = TimeZone::observed("wednesday", "0:13", 1_670_964_183).unwrap();
+    assert_eq!(ahead.offset_seconds, 3 * 3600 + 1800);
+    assert!(TimeZone::observed("tuesday", "tuesday", 0).is_err());
+    assert!(TimeZone::observed("20:39", "20:39", 0).is_err());
+    // 9:43 is either the next morning at +13:00 (tonga), or this morning at -11:00 (samoa)
+    let tonga = TimeZone::observed("wednesday", "9:43", 1_670_964_183).unwrap();
+    assert_eq!(tonga.offset_seconds, 13 * 3600);
+    let samoa = TimeZone::observed("tuesday", "9:43", 1_670_964_183).unwrap();
+    assert_eq!(samoa.offset_seconds, -11 * 3600);
+    let kiribati = TimeZone::observed("wednesday", "10:43", 1_670_964_183).unwrap();
+    assert_eq!(kiribati.offset_seconds, 14 * 3600);
+
+    let behind = TimeZone::observed("tuesday", "15:13", live_data.hub_time()).unwrap();
+    assert_eq!(behind.at(0).to_string(), "1969-12-31T18:30:00-05:30");
+    assert_eq!(
+        neohub::TimeZone::UTC.at(-3_155_747_655).to_string(),
+        "1869-12-31T03:25:45Z"
+    );
+}
+
+#[test]
+fn override_state() {
+    use neohub::OverrideState;
+
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"][0]["HOLD_ON"] = true.into();
+    live_data["devices"][0]["HOLD_TIME"] = "0:45".into();
+    live_data["devices"][1]["TEMPORARY_SET_FLAG"] = true.into();
+    live_data["devices"][1]["SET_TEMP"] = "19.5".into();
+    live_data["devices"][2]["HOLD_ON"] = true.into();
+    live_data["devices"][2]["HOLD_TIME"] = "0:45".into();
+    live_data["devices"][2]["STANDBY"] = true.into();
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+
+    let states = live_data.override_states().unwrap();
+    assert!(matches!(states["Office"], OverrideState::Hold(_)));
+    assert_eq!(
+        states["Top Floor"],
+        OverrideState::Temporary { temperature: 19.5 }
+    );
+    // standby wins over the hold
+    assert_eq!(states["Middle Floor"], OverrideState::Standby);
+    assert_eq!(states["Entryway"], OverrideState::None);
+}
+
+#[test]
+fn device_types() {
+    use neohub::DeviceType;
+
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"][1]["AVAILABLE_MODES"] = serde_json::json!(["heat", "cool", "auto"]);
+    live_data["devices"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({
+            "ZONE_NAME": "repeaternode54473",
+            "OFFLINE": false,
+        }));
+    let live_data: LiveData = serde_json::from_value(live_data).unwrap();
+
+    let types = live_data.device_types();
+    assert_eq!(types["Office"], DeviceType::Thermostat);
+    assert_eq!(types["Top Floor"], DeviceType::ThermostatHC);
+    assert_eq!(types["Hot Water"], DeviceType::Timeclock);
+    assert_eq!(types["repeaternode54473"], DeviceType::Repeater);
+    assert!(!types["Hot Water"].is_thermostat());
+    assert!(!DeviceType::Plug.is_thermostat());
+    assert_eq!(
+        serde_json::to_value(DeviceType::HotWater).unwrap(),
+        "HotWater"
+    );
+}
+
+#[test]
+fn from_text() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    let devices = live_data["devices"].as_array_mut().unwrap();
+    devices.push(serde_json::json!({
+        "ZONE_NAME": "repeaternode54473",
+        "OFFLINE": true,
+    }));
+    devices.push(serde_json::json!({
+        "ZONE_NAME": "The \"Den\"",
+        "DEVICE_ID": 7,
+    }));
+    let text = live_data.to_string();
+    let parsed: LiveData = serde_json::from_str(&text).unwrap();
+    assert_eq!(parsed.devices.len(), 6);
+    assert_eq!(parsed.repeaters.len(), 1);
+    assert_eq!(parsed.unparsed[0].zone_name.as_deref(), Some("The \"Den\""));
+    assert_eq!(parsed.unparsed[0].raw["DEVICE_ID"], 7);
+    assert_eq!(parsed, serde_json::from_value(live_data).unwrap());
+    assert_eq!(parsed, serde_json::from_slice(text.as_bytes()).unwrap());
+}
+
+#[test]
+fn system() {
+    let system: neohub::commands::System = serde_json::from_value(serde_json::json!({
+        "CORF": "C",
+        "FORMAT": "7DAY",
+        "TIME_ZONE": 5.5,
+        "HUB_VERSION": 2134,
+    }))
+    .unwrap();
+    assert_eq!(
+        system.temperature_unit(),
+        Some(neohub::TemperatureUnit::Celsius)
+    );
+    assert_eq!(system.format, Some(neohub::ScheduleFormat::SevenDay));
+    assert_eq!(system.extra["HUB_VERSION"], 2134);
+
+    use neohub::ScheduleFormat;
+    let format = |value| serde_json::from_value::<ScheduleFormat>(value);
+    assert_eq!(
+        format(serde_json::json!("5DAY")).unwrap(),
+        ScheduleFormat::WeekdaysWeekend
+    );
+    assert_eq!(
+        format(serde_json::json!(2)).unwrap(),
+        ScheduleFormat::WeekdaysWeekend
+    );
+    assert!(format(serde_json::json!(3)).is_err());
+    assert_eq!(
+        serde_json::to_value(ScheduleFormat::WeekdaysWeekend).unwrap(),
+        "5DAY"
+    );
+}
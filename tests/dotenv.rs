@@ -0,0 +1,41 @@
+#![cfg(feature = "dotenv")]
+
+use neohub::Client;
+
+// the only test in this file: it changes directory, and touches the environment
+#[test]
+fn dotenv() {
+    let dir = std::env::temp_dir().join(format!("neohub-dotenv-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+    for key in ["NEOHUB_URL", "NEOHUB_HOST", "NEOHUB_PORT", "NEOHUB_TOKEN"] {
+        std::env::remove_var(key);
+    }
+    std::env::set_var("NEOHUB_TIMEOUT_SECS", "2.5");
+
+    // no .env
+    assert!(Client::from_env().is_err());
+
+    std::fs::write(
+        ".env",
+        "# the hub\n\
+         export NEOHUB_HOST=\"192.168.1.2\"\n\
+         \n\
+         NEOHUB_PORT = '4243'\n\
+         NEOHUB_TOKEN=abc\n",
+    )
+    .unwrap();
+    assert_eq!(Client::from_env().unwrap().url(), "wss://192.168.1.2:4243");
+
+    // the environment wins
+    std::env::set_var("NEOHUB_PORT", "1234");
+    assert_eq!(Client::from_env().unwrap().url(), "wss://192.168.1.2:1234");
+
+    std::fs::write(".env", "NEOHUB_TOKEN=abc\nNEOHUB_HOST\n").unwrap();
+    let Err(err) = Client::from_env() else {
+        panic!("a line without a value was accepted");
+    };
+    assert!(err.to_string().contains(".env line 2"), "{err}");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
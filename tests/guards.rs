@@ -0,0 +1,218 @@
+use std::sync::{Arc, Mutex};
+
+use neohub::Client;
+
+fn hub() -> neohub::Builder {
+    Client::builder("wss://127.0.0.1:1", "token")
+}
+
+// a dry-run client, and the messages it would have sent
+fn recording_client(builder: neohub::Builder) -> (Client, Arc<Mutex<Vec<String>>>) {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let client = builder
+        .dry_run(true)
+        .on_command(move |record| sink.lock().unwrap().push(record.message.clone()))
+        .build()
+        .unwrap();
+    (client, records)
+}
+
+#[tokio::test]
+async fn writes_are_not_sent() {
+    // nothing is listening here, so any attempt to actually send would fail
+    let client = hub().dry_run(true).build().unwrap();
+    client.lock("1234", ["Office"]).await.unwrap();
+    client.set_preheat(2, ["Office"]).await.unwrap();
+}
+
+#[test]
+fn read_only_commands() {
+    assert!(neohub::commands::is_read_only(
+        neohub::commands::GET_LIVE_DATA
+    ));
+    assert!(!neohub::commands::is_read_only(neohub::commands::SET_TEMP));
+}
+
+#[tokio::test]
+async fn read_only_refuses_writes() {
+    let client = hub().read_only(true).build().unwrap();
+    let err = client.lock("1234", ["Office"]).await.unwrap_err();
+    assert!(err.to_string().contains("read-only"), "{err}");
+}
+
+#[tokio::test]
+async fn commands_are_recorded() {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let client = hub()
+        .dry_run(true)
+        .on_command(move |record| sink.lock().unwrap().push(record.clone()))
+        .build()
+        .unwrap();
+    client.unlock(["Office"]).await.unwrap();
+
+    let records = records.lock().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].command.as_deref(), Some("UNLOCK"));
+    assert_eq!(records[0].outcome, neohub::Outcome::DryRun);
+}
+
+#[tokio::test]
+async fn boost_is_sent_in_minutes() {
+    let (client, records) = recording_client(hub());
+    client
+        .boost(std::time::Duration::from_secs(2 * 60 * 60), ["Towel Rail"])
+        .await
+        .unwrap();
+    assert!(client
+        .boost(std::time::Duration::from_secs(30), ["Towel Rail"])
+        .await
+        .is_err());
+
+    let records = records.lock().unwrap();
+    assert_eq!(*records, ["{'TIMER_HOLD_ON':[120,['Towel Rail']]}"]);
+}
+
+#[tokio::test]
+async fn typed_commands() {
+    let (client, records) = recording_client(hub());
+    let ack = client
+        .send(&neohub::commands::SetPreheat {
+            value: 2,
+            zones: vec!["Office".to_string()],
+        })
+        .await
+        .unwrap();
+    assert_eq!(ack.result, "dry run");
+    client.send(&neohub::commands::CancelHoldAll).await.unwrap();
+    client
+        .send(&neohub::commands::SetTempFormat(
+            neohub::TemperatureUnit::Fahrenheit,
+        ))
+        .await
+        .unwrap();
+    client
+        .send(&neohub::commands::RunProfileId {
+            profile_id: 3,
+            zones: vec!["Office".to_string(), "Hall".to_string()],
+        })
+        .await
+        .unwrap();
+
+    let records = records.lock().unwrap();
+    assert_eq!(records[0], "{'SET_PREHEAT':[2,['Office']]}");
+    assert_eq!(records[1], "{'CANCEL_HOLD_ALL':0}");
+    assert_eq!(records[2], "{'SET_TEMP_FORMAT':'F'}");
+    assert_eq!(records[3], "{'RUN_PROFILE_ID':[3,'Office','Hall']}");
+}
+
+#[tokio::test]
+async fn identify_device() {
+    let (client, records) = recording_client(hub());
+    client.identify_device("Office").await.unwrap();
+
+    let records = records.lock().unwrap();
+    assert_eq!(records[0], "{'IDENTIFY_DEV':'Office'}");
+}
+
+#[tokio::test]
+async fn dangerous_commands() {
+    let client = hub().dry_run(true).build().unwrap();
+    assert!(client.danger_zone().is_err());
+    for message in [
+        "{'RESET':0}",
+        "{'DETACH_DEVICE':'Office'}",
+        "{'CLEAR_DEVICE_LIST':0}",
+        "{'SET_CHANNEL':15}",
+        "{'EXPIRE_TOKEN':'token'}",
+    ] {
+        let err = client.raw_message(message).await.unwrap_err();
+        assert!(
+            err.to_string().contains("allow_dangerous"),
+            "{message}: {err}"
+        );
+    }
+
+    let (client, records) = recording_client(hub().allow_dangerous(true));
+    let danger = client.danger_zone().unwrap();
+    danger.reboot().await.unwrap();
+    danger.remove_zone("Office").await.unwrap();
+    assert_eq!(
+        *records.lock().unwrap(),
+        ["{'RESET':0}", "{'REMOVE_ZONE':'Office'}"]
+    );
+}
+
+#[tokio::test]
+async fn hub_settings() {
+    use neohub::{ScheduleFormat, TemperatureUnit};
+
+    let (client, records) = recording_client(hub());
+    client
+        .set_temperature_unit(TemperatureUnit::Fahrenheit)
+        .await
+        .unwrap();
+    client
+        .set_schedule_format(ScheduleFormat::WeekdaysWeekend)
+        .await
+        .unwrap();
+    client.set_time_zone(5.5).await.unwrap();
+    assert!(client.set_time_zone(5.3).await.is_err());
+    client.set_ntp(true).await.unwrap();
+    assert_eq!(
+        *records.lock().unwrap(),
+        [
+            "{'SET_TEMP_FORMAT':'F'}",
+            "{'SET_FORMAT':'5DAY'}",
+            "{'TIME_ZONE':5.5}",
+            "{'NTP_ON':0}",
+        ]
+    );
+}
+
+struct Rewrite(&'static str);
+
+impl neohub::Middleware for Rewrite {
+    fn handle<'a>(
+        &'a self,
+        _msg: &'a str,
+        mut next: neohub::Next<'a>,
+    ) -> futures_util::future::BoxFuture<'a, anyhow::Result<(String, String)>> {
+        Box::pin(async move { next.run(self.0).await })
+    }
+}
+
+#[tokio::test]
+async fn guards_see_rewritten_messages() {
+    let client = hub().middleware(Rewrite("{'RESET':0}")).build().unwrap();
+    let err = client.raw_message("{'GET_ZONES':0}").await.unwrap_err();
+    assert!(err.to_string().contains("allow_dangerous"), "{err}");
+
+    let client = hub()
+        .read_only(true)
+        .middleware(Rewrite("{'SET_TEMP':[20,['Office']]}"))
+        .build()
+        .unwrap();
+    let err = client.raw_message("{'GET_ZONES':0}").await.unwrap_err();
+    assert!(err.to_string().contains("read-only"), "{err}");
+}
+
+#[tokio::test]
+async fn one_command_per_message() {
+    let client = hub().dry_run(true).build().unwrap();
+    let err = client
+        .raw_message("{'GET_ZONES':0,'RESET':0}")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("single command"), "{err}");
+    assert!(client.raw_message("GET_ZONES").await.is_err());
+}
+
+#[tokio::test]
+async fn quotes_are_refused() {
+    let client = hub().dry_run(true).build().unwrap();
+    assert!(client.set_temperature(20.0, ["Bob's room"]).await.is_err());
+    assert!(client.identify_device("Bob's room").await.is_err());
+    assert!(client.lock("1234", ["The \"Den\""]).await.is_err());
+}
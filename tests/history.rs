@@ -0,0 +1,83 @@
+#![cfg(feature = "history")]
+
+use std::time::Duration;
+
+use neohub::history::{downsample, History};
+use neohub::LiveData;
+
+#[test]
+fn record_and_query() {
+    let path = std::env::temp_dir().join(format!("neohub-history-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    let mut history = History::open(&path).unwrap();
+    for (i, temp) in ["20.0", "21.0", "23.0"].into_iter().enumerate() {
+        live_data["HUB_TIME"] = (1663101780 + 60 * i as i64).into();
+        live_data["devices"][0]["ACTUAL_TEMP"] = temp.into();
+        let snapshot: LiveData = serde_json::from_value(live_data.clone()).unwrap();
+        history.record(&snapshot).unwrap();
+    }
+
+    let samples = history.query("Office", 0..i64::MAX).unwrap();
+    assert_eq!(samples.len(), 3);
+    assert_eq!(samples[1].temperature, Some(21.0));
+    assert_eq!(
+        history.query("Office", 1663101840..i64::MAX).unwrap().len(),
+        2
+    );
+
+    let buckets = downsample(&samples, Duration::from_secs(120));
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(buckets[0].temperature, Some(20.0));
+    assert_eq!(buckets[1].temperature, Some(22.0));
+}
+
+#[test]
+fn torn_lines_and_sensors() {
+    use std::io::Write;
+
+    let path = std::env::temp_dir().join(format!("neohub-torn-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    live_data["devices"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({
+            "ZONE_NAME": "Back Door",
+            "DEVICE_ID": 7,
+            "WINDOW_OPEN": true,
+            "LOW_BATTERY": false,
+            "OFFLINE": false,
+        }));
+    let snapshot: LiveData = serde_json::from_value(live_data.clone()).unwrap();
+
+    let mut history = History::open(&path).unwrap();
+    history.record(&snapshot).unwrap();
+    let samples = history.query("Back Door", 0..i64::MAX).unwrap();
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].window_open, Some(true));
+    assert_eq!(samples[0].temperature, None);
+
+    // the power went mid-write
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(br#"{"time": 16631"#).unwrap();
+    drop(file);
+    assert_eq!(history.query("Office", 0..i64::MAX).unwrap().len(), 1);
+    assert_eq!(history.corrupt_lines(), 0);
+
+    // and on restart, recording carries on after it
+    let mut history = History::open(&path).unwrap();
+    history.record(&snapshot).unwrap();
+    assert_eq!(history.query("Office", 0..i64::MAX).unwrap().len(), 2);
+    assert_eq!(history.corrupt_lines(), 1);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,689 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use neohub::{Client, ConnectionEvent};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+// a hub which sends `respond(command_id, command)` in reply to each command, after
+// `delay(command)`
+async fn mock_hub(
+    respond: impl Fn(&Value, &str) -> Vec<Value> + Send + Sync + 'static,
+    delay: impl Fn(&str) -> Duration + Send + Sync + 'static,
+) -> String {
+    mock_hub_on("127.0.0.1:0", respond, delay).await
+}
+
+async fn mock_hub_on(
+    address: &str,
+    respond: impl Fn(&Value, &str) -> Vec<Value> + Send + Sync + 'static,
+    delay: impl Fn(&str) -> Duration + Send + Sync + 'static,
+) -> String {
+    let listener = TcpListener::bind(address).await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let respond = Arc::new(respond);
+    let delay = Arc::new(delay);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let respond = respond.clone();
+            let delay = delay.clone();
+            tokio::spawn(async move {
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                while let Some(Ok(msg)) = ws.next().await {
+                    let Message::Text(msg) = msg else {
+                        continue;
+                    };
+                    let outer: Value = serde_json::from_str(&msg).unwrap();
+                    let middle: Value =
+                        serde_json::from_str(outer["message"].as_str().unwrap()).unwrap();
+                    let command = &middle["COMMANDS"][0];
+                    let text = command["COMMAND"].as_str().unwrap();
+                    tokio::time::sleep(delay(text)).await;
+                    for frame in respond(&command["COMMANDID"], text) {
+                        ws.send(Message::Text(frame.to_string())).await.unwrap();
+                    }
+                }
+            });
+        }
+    });
+    url
+}
+
+fn reply(command_id: &Value, response: &str) -> Value {
+    json!({
+        "command_id": command_id,
+        "device_id": "hub",
+        "message_type": "hm_set_command_response",
+        "response": response,
+    })
+}
+
+// responds with the command, without quotes
+fn echo(command_id: &Value, command: &str) -> Vec<Value> {
+    let response = format!(r#"{{"result": "{}"}}"#, command.replace('\'', ""));
+    vec![reply(command_id, &response)]
+}
+
+#[tokio::test]
+async fn late_responses_are_discarded() {
+    let url = mock_hub(echo, |command| {
+        if command.contains("SLOW") {
+            Duration::from_millis(250)
+        } else {
+            Duration::ZERO
+        }
+    })
+    .await;
+    let client = Client::builder(url, "token")
+        .timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+
+    assert!(client.raw_message("{'SLOW':0}").await.is_err());
+    let (_, resp) = client.raw_message("{'FAST':0}").await.unwrap();
+    assert_eq!(resp, r#"{"result": "{FAST:0}"}"#);
+}
+
+#[tokio::test]
+async fn unsolicited_messages_are_ignored() {
+    let url = mock_hub(
+        |command_id, command| {
+            let mut frames = vec![
+                json!({"message_type": "hm_event", "event": "something happened"}),
+                reply(&json!(9999), r#"{"result": "not yours"}"#),
+            ];
+            frames.extend(echo(command_id, command));
+            frames
+        },
+        |_| Duration::ZERO,
+    )
+    .await;
+    let client = Client::new(url, "token").unwrap();
+
+    for _ in 0..2 {
+        let (_, resp) = client.raw_message("{'FIRMWARE':0}").await.unwrap();
+        assert_eq!(resp, r#"{"result": "{FIRMWARE:0}"}"#);
+    }
+}
+
+#[tokio::test]
+async fn websocket_config() {
+    let url = mock_hub(
+        |command_id, _| vec![reply(command_id, &"x".repeat(4096))],
+        |_| Duration::ZERO,
+    )
+    .await;
+    let config = neohub::WebSocketConfig {
+        max_message_size: Some(1024),
+        ..Default::default()
+    };
+    let client = Client::builder(url, "token")
+        .websocket_config(config)
+        .build()
+        .unwrap();
+    assert!(client.raw_message("{'FIRMWARE':0}").await.is_err());
+}
+
+// an http CONNECT proxy, which checks it's asked for `expected_target`
+async fn mock_http_proxy(expected_target: String) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://user:pass@{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut client, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(client.read_u8().await.unwrap());
+        }
+        let request = String::from_utf8(request).unwrap();
+        assert!(
+            request.starts_with(&format!("CONNECT {expected_target} HTTP/1.1\r\n")),
+            "{request}"
+        );
+        assert!(
+            request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"),
+            "{request}"
+        );
+        let mut target = TcpStream::connect(expected_target).await.unwrap();
+        client
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .await
+            .unwrap();
+        tokio::io::copy_bidirectional(&mut client, &mut target)
+            .await
+            .ok();
+    });
+    url
+}
+
+// a socks5 proxy, without authentication, which sends each target it's asked for to
+// `targets`, as an address or a name
+async fn mock_socks5_proxy(scheme: &str, targets: mpsc::UnboundedSender<String>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("{scheme}://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut client, _) = listener.accept().await.unwrap();
+        let mut greeting = [0; 3];
+        client.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(greeting, [5, 1, 0]);
+        client.write_all(&[5, 0]).await.unwrap();
+
+        let mut request = [0; 4];
+        client.read_exact(&mut request).await.unwrap();
+        assert_eq!(request[..3], [5, 1, 0]);
+        let host = match request[3] {
+            1 => {
+                let mut ip = [0; 4];
+                client.read_exact(&mut ip).await.unwrap();
+                std::net::Ipv4Addr::from(ip).to_string()
+            }
+            4 => {
+                let mut ip = [0; 16];
+                client.read_exact(&mut ip).await.unwrap();
+                std::net::Ipv6Addr::from(ip).to_string()
+            }
+            3 => {
+                let mut name = vec![0; client.read_u8().await.unwrap() as usize];
+                client.read_exact(&mut name).await.unwrap();
+                String::from_utf8(name).unwrap()
+            }
+            other => panic!("address type {other}"),
+        };
+        let port = client.read_u16().await.unwrap();
+        targets.send(host.clone()).unwrap();
+        let Ok(mut target) = TcpStream::connect((host, port)).await else {
+            // host unreachable
+            client.write_all(&[5, 4, 0, 1, 0, 0, 0, 0, 0, 0]).await.ok();
+            return;
+        };
+        client
+            .write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+        tokio::io::copy_bidirectional(&mut client, &mut target)
+            .await
+            .ok();
+    });
+    url
+}
+
+#[tokio::test]
+async fn http_proxy() {
+    let url = mock_hub(echo, |_| Duration::ZERO).await;
+    let proxy = mock_http_proxy(url.trim_start_matches("ws://").to_string()).await;
+    let client = Client::builder(url, "token").proxy(proxy).build().unwrap();
+    let (_, resp) = client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    assert_eq!(resp, r#"{"result": "{FIRMWARE:0}"}"#);
+}
+
+#[tokio::test]
+async fn socks5_proxy() {
+    let url = mock_hub(echo, |_| Duration::ZERO).await;
+    let (tx, mut targets) = mpsc::unbounded_channel();
+    let proxy = mock_socks5_proxy("socks5", tx).await;
+    let client = Client::builder(url, "token").proxy(proxy).build().unwrap();
+    let (_, resp) = client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    assert_eq!(resp, r#"{"result": "{FIRMWARE:0}"}"#);
+    assert_eq!(targets.recv().await.unwrap(), "127.0.0.1");
+
+    assert!(Client::builder("wss://127.0.0.1:1", "token")
+        .proxy("ftp://127.0.0.1:1")
+        .build()
+        .is_err());
+}
+
+#[tokio::test]
+async fn socks5_dns() {
+    let url = mock_hub(echo, |_| Duration::ZERO).await;
+    let url = url.replace("127.0.0.1", "localhost");
+
+    // looked up here
+    let (tx, mut targets) = mpsc::unbounded_channel();
+    let proxy = mock_socks5_proxy("socks5", tx).await;
+    let client = Client::builder(&url, "token").proxy(proxy).build().unwrap();
+    // which may be ::1, where the hub isn't listening
+    let _ = client.raw_message("{'FIRMWARE':0}").await;
+    let target: std::net::IpAddr = targets.recv().await.unwrap().parse().unwrap();
+    assert!(target.is_loopback(), "{target}");
+
+    // by the proxy
+    let (tx, mut targets) = mpsc::unbounded_channel();
+    let proxy = mock_socks5_proxy("socks5h", tx).await;
+    let client = Client::builder(&url, "token").proxy(proxy).build().unwrap();
+    let (_, resp) = client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    assert_eq!(resp, r#"{"result": "{FIRMWARE:0}"}"#);
+    assert_eq!(targets.recv().await.unwrap(), "localhost");
+}
+
+#[tokio::test]
+async fn pre_resolved_address() {
+    let url = mock_hub(echo, |_| Duration::ZERO).await;
+    let address: std::net::SocketAddr = url.trim_start_matches("ws://").parse().unwrap();
+    let client = Client::builder(format!("ws://neohub.invalid:{}", address.port()), "token")
+        .address(address)
+        .build()
+        .unwrap();
+    let (_, resp) = client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    assert_eq!(resp, r#"{"result": "{FIRMWARE:0}"}"#);
+}
+
+#[tokio::test]
+async fn ipv6() {
+    let url = mock_hub_on("[::1]:0", echo, |_| Duration::ZERO).await;
+    assert!(url.starts_with("ws://[::1]:"), "{url}");
+    let client = Client::new(url, "token").unwrap();
+    let (_, resp) = client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    assert_eq!(resp, r#"{"result": "{FIRMWARE:0}"}"#);
+}
+
+#[tokio::test]
+async fn invalid_token() {
+    let url = mock_hub(
+        |_, _| vec![json!({"message_type": "error", "message": "Invalid token"})],
+        |_| Duration::ZERO,
+    )
+    .await;
+    let client = Client::new(url, "wrong").unwrap();
+    let err = client.verify_token().await.unwrap_err();
+    assert!(
+        err.downcast_ref::<neohub::InvalidToken>().is_some(),
+        "{err:#}"
+    );
+
+    let url = mock_hub(
+        |command_id, _| vec![reply(command_id, r#"{"firmware version": "2134"}"#)],
+        |_| Duration::ZERO,
+    )
+    .await;
+    let client = Client::new(url, "right").unwrap();
+    client.verify_token().await.unwrap();
+}
+
+#[tokio::test]
+async fn connect_retry() {
+    // find a free port, then only start the hub after a while
+    let address = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        mock_hub_on(&address.to_string(), echo, |_| Duration::ZERO).await;
+    });
+
+    let client = Client::builder(format!("ws://{address}"), "token")
+        .connect_retry(5, Duration::from_millis(100))
+        .build_lazy()
+        .unwrap();
+    let (_, resp) = client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    assert_eq!(resp, r#"{"result": "{FIRMWARE:0}"}"#);
+}
+
+#[tokio::test]
+async fn connect_once_lazily() {
+    // nothing listening
+    let address = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let client = Client::builder(format!("ws://{address}"), "token")
+        .connect_retry(1, Duration::from_secs(10))
+        .build_lazy()
+        .unwrap();
+    let result = tokio::time::timeout(Duration::from_secs(5), client.connect()).await;
+    assert!(
+        matches!(result, Ok(Err(_))),
+        "retried despite connect_retry(1, ..)"
+    );
+}
+
+#[tokio::test]
+async fn ping() {
+    let url = mock_hub(echo, |_| Duration::ZERO).await;
+    let client = Client::new(url, "token").unwrap();
+    assert!(!client.is_connected());
+    client.ping().await.unwrap();
+    assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn liveness_check() {
+    // stops reading entirely, like a hub which has silently gone away
+    let url = mock_hub(echo, |command| {
+        if command.contains("STALL") {
+            Duration::from_secs(3600)
+        } else {
+            Duration::ZERO
+        }
+    })
+    .await;
+    let client = Client::builder(url, "token")
+        .timeout(Duration::from_millis(500))
+        .liveness_check(Duration::ZERO, Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    client.raw_message("{'FAST':0}").await.unwrap();
+    assert!(client.raw_message("{'STALL':0}").await.is_err());
+    let (_, resp) = client.raw_message("{'FAST':0}").await.unwrap();
+    assert_eq!(resp, r#"{"result": "{FAST:0}"}"#);
+}
+
+#[tokio::test]
+async fn pool() {
+    let url = mock_hub(echo, |_| Duration::from_millis(50)).await;
+    let pool = neohub::Pool::new(Client::builder(url, "token"), 2);
+    let results = futures_util::future::join_all((0..4).map(|i| {
+        let pool = &pool;
+        async move {
+            let client = pool.get().await?;
+            client.raw_message(&format!("{{'CMD{i}':0}}")).await
+        }
+    }))
+    .await;
+    for (i, result) in results.into_iter().enumerate() {
+        let (_, resp) = result.unwrap();
+        assert_eq!(resp, format!(r#"{{"result": "{{CMD{i}:0}}"}}"#));
+    }
+}
+
+#[tokio::test]
+async fn proxy_server() {
+    let url = mock_hub(echo, |_| Duration::from_millis(20)).await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let local = format!("ws://{}", listener.local_addr().unwrap());
+    let server = neohub::Server::new(Client::new(url, "hub token").unwrap(), "local");
+    tokio::spawn(server.serve(listener));
+
+    let results = futures_util::future::join_all((0..3).map(|i| {
+        let local = local.clone();
+        async move {
+            let client = Client::new(local, "local")?;
+            client.raw_message(&format!("{{'CMD{i}':0}}")).await
+        }
+    }))
+    .await;
+    for (i, result) in results.into_iter().enumerate() {
+        let (device_id, resp) = result.unwrap();
+        assert_eq!(device_id, "hub");
+        assert_eq!(resp, format!(r#"{{"result": "{{CMD{i}:0}}"}}"#));
+    }
+
+    let client = Client::new(local.clone(), "wrong").unwrap();
+    let err = client.verify_token().await.unwrap_err();
+    assert!(
+        err.downcast_ref::<neohub::InvalidToken>().is_some(),
+        "{err:#}"
+    );
+
+    // a garbled frame gets an error, and the session carries on
+    let (mut ws, _) = tokio_tungstenite::connect_async(&local).await.unwrap();
+    ws.send(Message::Text("not json".into())).await.unwrap();
+    let Some(Ok(Message::Text(error))) = ws.next().await else {
+        panic!("no error frame");
+    };
+    let error: Value = serde_json::from_str(&error).unwrap();
+    assert_eq!(error["message_type"], "error");
+    let request = neohub::protocol::Session::new("local")
+        .request("{'CMD':0}")
+        .unwrap();
+    ws.send(Message::Text(request.frame)).await.unwrap();
+    let Some(Ok(Message::Text(resp))) = ws.next().await else {
+        panic!("no response");
+    };
+    let resp: Value = serde_json::from_str(&resp).unwrap();
+    assert_eq!(resp["response"], r#"{"result": "{CMD:0}"}"#);
+}
+
+#[tokio::test]
+async fn timestamp_cache() {
+    use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+    let changed = Arc::new(AtomicI64::new(1));
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let other_fetches = Arc::new(AtomicUsize::new(0));
+    let url = {
+        let (changed, fetches, other_fetches) =
+            (changed.clone(), fetches.clone(), other_fetches.clone());
+        mock_hub(
+            move |command_id, command| {
+                let response = match command {
+                    "{'GET_LIVE_DATA':0}" => {
+                        let mut live: Value =
+                            serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+                        let changed = changed.load(Ordering::SeqCst);
+                        live["TIMESTAMP_RECIPES"] = changed.into();
+                        if changed > 2 {
+                            // as older firmware
+                            live.as_object_mut().unwrap().remove("TIMESTAMP_SYSTEM");
+                        }
+                        live
+                    }
+                    "{'GET_ZONES':0}" => {
+                        other_fetches.fetch_add(1, Ordering::SeqCst);
+                        json!({"Office": 1})
+                    }
+                    "{'GET_SYSTEM':0}" => {
+                        other_fetches.fetch_add(1, Ordering::SeqCst);
+                        json!({"CORF": "C"})
+                    }
+                    "{'GET_RECIPES':0}" => {
+                        fetches.fetch_add(1, Ordering::SeqCst);
+                        json!({"Away": []})
+                    }
+                    other => panic!("unexpected {other}"),
+                };
+                vec![reply(command_id, &response.to_string())]
+            },
+            |_| Duration::ZERO,
+        )
+        .await
+    };
+    let client = Client::builder(url, "token")
+        .generation(neohub::Generation::Second)
+        .build()
+        .unwrap();
+    let mut cache = neohub::Cache::new();
+
+    assert_eq!(cache.recipes(&client).await.unwrap()[0].name, "Away");
+    cache.recipes(&client).await.unwrap();
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+    changed.store(2, Ordering::SeqCst);
+    cache.recipes(&client).await.unwrap();
+    assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+    // observed live data saves fetching the timestamps, too
+    let live_data = client.live_data().await.unwrap();
+    assert_eq!(live_data.timestamps().recipes, 2);
+    cache.observe(&live_data);
+    cache.recipes(&client).await.unwrap();
+    assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+    for _ in 0..2 {
+        assert_eq!(cache.zones(&client).await.unwrap().id("Office"), Some(1));
+        assert_eq!(
+            cache.system(&client).await.unwrap().corf.as_deref(),
+            Some("C")
+        );
+    }
+    assert_eq!(other_fetches.load(Ordering::SeqCst), 2);
+    // without a system timestamp, there's no telling whether it's changed
+    changed.store(3, Ordering::SeqCst);
+    cache.system(&client).await.unwrap();
+    cache.system(&client).await.unwrap();
+    assert_eq!(other_fetches.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn diagnostics() {
+    let url = mock_hub(
+        |command_id, command| {
+            let response = match command {
+                "{'FIRMWARE':0}" => json!({"firmware version": "2134"}),
+                "{'GET_SYSTEM':0}" => json!({"HUB_VERSION": 2134}),
+                "{'STATISTICS':0}" => json!({"error": "Unknown command"}),
+                "{'GET_HOURSRUN':0}" => json!({"Office": [1, 2]}),
+                "{'GET_ZONES':0}" => json!({"Office": 1}),
+                "{'GET_TEMPLOG':['Office']}" => json!({"Office": "[19.5, 20.0]"}),
+                "{'OFFLINE_DEVICES':0}" => json!({}),
+                other => panic!("unexpected {other}"),
+            };
+            vec![reply(command_id, &response.to_string())]
+        },
+        |_| Duration::ZERO,
+    )
+    .await;
+    let client = Client::new(url, "token").unwrap();
+    let diagnostics = client.diagnostics().await.unwrap();
+    assert_eq!(diagnostics.firmware_version.as_deref(), Some("2134"));
+    assert_eq!(diagnostics.statistics, None);
+    assert!(diagnostics.errors["STATISTICS"].contains("Unknown command"));
+    assert_eq!(diagnostics.hours_run, Some(json!({"Office": [1, 2]})));
+    assert_eq!(
+        diagnostics.temperature_log,
+        Some(json!({"Office": [19.5, 20.0]}))
+    );
+}
+
+#[tokio::test]
+async fn diagnostics_without_zones() {
+    let url = mock_hub(
+        |command_id, command| {
+            let response = match command {
+                "{'FIRMWARE':0}" => json!({"firmware version": "2134"}),
+                "{'GET_ZONES':0}" => json!({"error": "Unknown command"}),
+                _ => json!({}),
+            };
+            vec![reply(command_id, &response.to_string())]
+        },
+        |_| Duration::ZERO,
+    )
+    .await;
+    let client = Client::new(url, "token").unwrap();
+    let diagnostics = client.diagnostics().await.unwrap();
+    assert_eq!(diagnostics.temperature_log, None);
+    assert!(diagnostics.errors["GET_TEMPLOG"].starts_with("listing zones"));
+    assert_eq!(diagnostics.offline_devices, Some(json!({})));
+}
+
+#[tokio::test]
+async fn zone_group() {
+    let url = mock_hub(
+        |command_id, command| {
+            if command.contains("Bath") {
+                vec![reply(command_id, r#"{"error": "Invalid argument"}"#)]
+            } else {
+                echo(command_id, command)
+            }
+        },
+        |_| Duration::from_millis(20),
+    )
+    .await;
+    let client = Client::new(url, "token").unwrap();
+    let upstairs = neohub::ZoneGroup::new("Upstairs", ["Bed1", "Bed2", "Bath"]);
+
+    let report = upstairs.set_temperature(&client, 19.5).await;
+    assert_eq!(report.results.len(), 3);
+    assert!(report.results["Bed1"].is_ok());
+    assert!(report.results["Bed2"].is_ok());
+    let failed = report.failed();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].0, "Bath");
+    assert!(failed[0].1.downcast_ref::<neohub::HubRejected>().is_some());
+}
+
+#[tokio::test]
+async fn shared_client() {
+    let url = mock_hub(echo, |_| Duration::from_millis(10)).await;
+    let client = Arc::new(Client::new(url, "token").unwrap());
+    let tasks = (0..4).map(|i| {
+        let client = client.clone();
+        tokio::spawn(async move { client.raw_message(&format!("{{'CMD{i}':0}}")).await })
+    });
+    for (i, task) in futures_util::future::join_all(tasks)
+        .await
+        .into_iter()
+        .enumerate()
+    {
+        let (_, resp) = task.unwrap().unwrap();
+        assert_eq!(resp, format!(r#"{{"result": "{{CMD{i}:0}}"}}"#));
+    }
+    assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn max_message_size() {
+    let url = mock_hub(
+        |command_id, _| vec![reply(command_id, &"x".repeat(4096))],
+        |_| Duration::ZERO,
+    )
+    .await;
+    let client = Client::builder(&url, "token")
+        .max_message_size(1024)
+        .build()
+        .unwrap();
+    assert!(client.raw_message("{'FIRMWARE':0}").await.is_err());
+
+    let client = Client::builder(&url, "token")
+        .max_message_size(None)
+        .build()
+        .unwrap();
+    let (_, resp) = client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    assert_eq!(resp.len(), 4096);
+}
+
+#[tokio::test]
+async fn connection_events() {
+    // closes the connection on `BYE`
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(Message::Text(msg))) = ws.next().await {
+                if msg.contains("BYE") {
+                    ws.close(None).await.unwrap();
+                    break;
+                }
+                let outer: Value = serde_json::from_str(&msg).unwrap();
+                let middle: Value =
+                    serde_json::from_str(outer["message"].as_str().unwrap()).unwrap();
+                let command = &middle["COMMANDS"][0];
+                let text = command["COMMAND"].as_str().unwrap();
+                for frame in echo(&command["COMMANDID"], text) {
+                    ws.send(Message::Text(frame.to_string())).await.unwrap();
+                }
+            }
+        }
+    });
+
+    let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorder = events.clone();
+    let client = Client::builder(url, "token")
+        .on_event(move |event| recorder.lock().unwrap().push(event))
+        .build()
+        .unwrap();
+    assert!(client.raw_message("{'BYE':0}").await.is_err());
+    client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    client.disconnect().await.unwrap();
+
+    let disconnected = |reason: &str| ConnectionEvent::Disconnected {
+        reason: reason.to_string(),
+    };
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            ConnectionEvent::Connected,
+            disconnected("closed by the hub"),
+            ConnectionEvent::Reconnecting { attempt: 1 },
+            ConnectionEvent::Connected,
+            disconnected("disconnected by the client"),
+        ]
+    );
+}
@@ -0,0 +1,458 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures_util::future::BoxFuture;
+use neohub::protocol::{decode_request, encode_response};
+use neohub::{Client, Generation, LiveData, Middleware, Next, Transport};
+use tokio::sync::mpsc;
+
+// a hub, in memory, answering each command with `answer(command)`, until it's sent `BYE`
+struct Memory {
+    to_hub: mpsc::UnboundedSender<String>,
+    from_hub: tokio::sync::Mutex<mpsc::UnboundedReceiver<String>>,
+}
+
+impl Memory {
+    fn hub(answer: impl Fn(&str) -> String + Send + 'static) -> Self {
+        let (to_hub, mut requests) = mpsc::unbounded_channel::<String>();
+        let (responses, from_hub) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(frame) = requests.recv().await {
+                for queued in decode_request(&frame).unwrap().commands {
+                    // hang up
+                    if queued.command == "{'BYE':0}" {
+                        return;
+                    }
+                    let response = answer(&queued.command);
+                    drop(responses.send(encode_response(&queued.command_id, "hub", &response)));
+                }
+            }
+        });
+        Memory {
+            to_hub,
+            from_hub: tokio::sync::Mutex::new(from_hub),
+        }
+    }
+}
+
+impl Transport for Memory {
+    fn send(&self, frame: String) -> BoxFuture<'_, Result<()>> {
+        let sent = self.to_hub.send(frame).map_err(|_| anyhow!("hub stopped"));
+        Box::pin(async move { sent })
+    }
+
+    fn recv(&self) -> BoxFuture<'_, Result<Option<String>>> {
+        Box::pin(async move { Ok(self.from_hub.lock().await.recv().await) })
+    }
+}
+
+// a client for a hub which answers every command with `response`
+fn canned(response: impl ToString) -> neohub::Builder {
+    let response = response.to_string();
+    Client::builder("wss://127.0.0.1:1", "token").transport(move || {
+        let response = response.clone();
+        async move { Ok(Memory::hub(move |_| response.clone())) }
+    })
+}
+
+// a dry-run client, and the messages it would have sent
+fn recording_client(builder: neohub::Builder) -> (Client, Arc<Mutex<Vec<String>>>) {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let client = builder
+        .dry_run(true)
+        .on_command(move |record| sink.lock().unwrap().push(record.message.clone()))
+        .build()
+        .unwrap();
+    (client, records)
+}
+
+// answers everything itself, so nothing is sent
+struct Canned(String);
+
+impl Middleware for Canned {
+    fn handle<'a>(
+        &'a self,
+        _msg: &'a str,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, Result<(String, String)>> {
+        Box::pin(async move { Ok(("hub".to_string(), self.0.to_string())) })
+    }
+}
+
+#[tokio::test]
+async fn short_circuit() {
+    let client = Client::builder("wss://127.0.0.1:1", "token")
+        .middleware(Canned(include_str!("live-data-1.json").to_string()))
+        .build()
+        .unwrap();
+    let live_data: LiveData = client
+        .command_void(neohub::commands::GET_LIVE_DATA)
+        .await
+        .unwrap();
+    assert_eq!(live_data.devices.len(), 6);
+}
+
+#[tokio::test]
+async fn strict() {
+    let client = canned(include_str!("live-data-1.json"))
+        .strict(true)
+        .generation(Generation::Second)
+        .build()
+        .unwrap();
+    client.live_data().await.unwrap();
+
+    let client = canned(include_str!("live-data-1.json").replacen('{', r#"{"NEW_THING": 1,"#, 1))
+        .strict(true)
+        .generation(Generation::Second)
+        .build()
+        .unwrap();
+    let Err(err) = client.live_data().await else {
+        panic!("unknown field accepted");
+    };
+    assert!(format!("{err:#}").contains("NEW_THING"), "{err:#}");
+}
+
+#[tokio::test]
+async fn first_generation_defaults() {
+    let client = canned(
+        r#"{"HUB_TIME": 1663101783, "devices": [
+                {"ZONE_NAME": "Office", "DEVICE_ID": 1, "ACTUAL_TEMP": "20.1", "SET_TEMP": "21.0"}
+            ]}"#,
+    )
+    .generation(Generation::First)
+    .build()
+    .unwrap();
+    let live_data = client.live_data().await.unwrap();
+    assert_eq!(live_data.devices[0].zone_name, "Office");
+}
+
+#[test]
+fn generation_from_firmware() {
+    assert_eq!(Generation::from_firmware("2134"), Some(Generation::Second));
+    assert_eq!(Generation::from_firmware("408"), Some(Generation::First));
+    assert_eq!(Generation::from_firmware("beta"), None);
+}
+
+#[tokio::test]
+async fn hub_rejected() {
+    let client = canned(r#"{"error": "Invalid argument"}"#).build().unwrap();
+    let err = client.set_preheat(2, ["Nowhere"]).await.unwrap_err();
+    let rejected = err
+        .downcast_ref::<neohub::HubRejected>()
+        .unwrap_or_else(|| panic!("{err:#}"));
+    assert_eq!(rejected.command, "SET_PREHEAT");
+    assert_eq!(rejected.reason, "Invalid argument");
+}
+
+#[tokio::test]
+async fn device_id() {
+    let client = canned(r#"{"HUB_VERSION": 2}"#).build().unwrap();
+    let resp: neohub::Response<serde_json::Value> = client
+        .command_void_response(neohub::commands::GET_SYSTEM)
+        .await
+        .unwrap();
+    assert_eq!(resp.device_id, "hub");
+    assert_eq!(resp.value["HUB_VERSION"], 2);
+}
+
+#[tokio::test]
+async fn command_value() {
+    let client = canned(r#"{"devices": "[{\"id\": 1}]"}"#).build().unwrap();
+    let value = client
+        .command_value("GET_DEVICE_LIST", serde_json::json!("Office"))
+        .await
+        .unwrap();
+    assert_eq!(value["devices"][0]["id"], 1);
+
+    assert!(client
+        .command_value("SET_TITLE", serde_json::json!(["Bob's room"]))
+        .await
+        .is_err());
+    assert!(client
+        .command_value("{'GET_ZONES':0}", serde_json::Value::Null)
+        .await
+        .is_err());
+}
+
+// counts the messages which get this far
+struct Counting(Arc<AtomicUsize>);
+
+impl Middleware for Counting {
+    fn handle<'a>(
+        &'a self,
+        _msg: &'a str,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, Result<(String, String)>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move { Ok(("hub".to_string(), r#"{"Office": 1}"#.to_string())) })
+    }
+}
+
+#[tokio::test]
+async fn cached_client() {
+    let sent = Arc::new(AtomicUsize::new(0));
+    let builder = Client::builder("wss://127.0.0.1:1", "token").middleware(Counting(sent.clone()));
+    let client = neohub::CachedClient::new(
+        builder,
+        [(neohub::commands::GET_ZONES, Duration::from_secs(60))],
+    )
+    .unwrap();
+
+    for _ in 0..3 {
+        let _: serde_json::Value = client
+            .command_void(neohub::commands::GET_ZONES)
+            .await
+            .unwrap();
+    }
+    assert_eq!(sent.load(Ordering::SeqCst), 1);
+
+    // not cached
+    let _: serde_json::Value = client
+        .command_void(neohub::commands::GET_SYSTEM)
+        .await
+        .unwrap();
+    assert_eq!(sent.load(Ordering::SeqCst), 2);
+
+    client.invalidate();
+    let _: serde_json::Value = client
+        .command_void(neohub::commands::GET_ZONES)
+        .await
+        .unwrap();
+    assert_eq!(sent.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn profiles_by_name() {
+    let profiles = format!(r#"{{"Winter": {}}}"#, include_str!("profile-1.json"));
+    let (client, records) = recording_client(canned(profiles));
+
+    assert_eq!(client.profile_names().await.unwrap()["Winter"], 1);
+    assert_eq!(client.profile(1).await.unwrap().name, "Winter");
+    assert!(client.profile("Summer").await.is_err());
+
+    client.delete_profile("Winter").await.unwrap();
+    client.delete_profile(7).await.unwrap();
+    let records = records.lock().unwrap();
+    assert_eq!(records[records.len() - 2], "{'CLEAR_PROFILE_ID':1}");
+    assert_eq!(records[records.len() - 1], "{'CLEAR_PROFILE_ID':7}");
+}
+
+#[tokio::test]
+async fn run_profile() {
+    let (client, records) =
+        recording_client(canned(include_str!("live-data-1.json")).generation(Generation::Second));
+
+    client.run_profile(2, ["Office", "Entryway"]).await.unwrap();
+    assert_eq!(
+        records.lock().unwrap()[0],
+        "{'RUN_PROFILE_ID':[2,'Office','Entryway']}"
+    );
+
+    let active = client.active_profiles().await.unwrap();
+    assert_eq!(active["Office"], Some(3));
+    assert_eq!(active["Hot Water"], None);
+}
+
+#[tokio::test]
+async fn all_standby() {
+    let (client, records) =
+        recording_client(canned(include_str!("live-data-1.json")).generation(Generation::Second));
+
+    let zones = client.all_standby().await.unwrap();
+    assert_eq!(zones.len(), 5);
+    assert!(!zones.iter().any(|zone| zone == "Hot Water"));
+    client.all_resume().await.unwrap();
+
+    let records = records.lock().unwrap();
+    assert!(
+        records[1].starts_with("{'FROST_ON':['Office',"),
+        "{}",
+        records[1]
+    );
+    assert!(
+        records[3].starts_with("{'FROST_OFF':['Office',"),
+        "{}",
+        records[3]
+    );
+}
+
+#[tokio::test]
+async fn fahrenheit_hub() {
+    let (client, records) = recording_client(canned(r#"{"CORF": "F"}"#));
+
+    assert_eq!(
+        client.temperature_unit().await.unwrap(),
+        neohub::TemperatureUnit::Fahrenheit
+    );
+    client.set_temp_c(21.0, ["Office"]).await.unwrap();
+    assert_eq!(
+        client
+            .from_hub_unit(50.0, neohub::TemperatureUnit::Celsius)
+            .await
+            .unwrap(),
+        10.0
+    );
+
+    let records = records.lock().unwrap();
+    // detected once
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1], "{'SET_TEMP':[70.0,['Office']]}");
+}
+
+#[tokio::test]
+async fn raw_frames() {
+    let client = canned(r#"{"devices": "not a list"}"#)
+        .generation(Generation::Second)
+        .build()
+        .unwrap();
+    let err = client.live_data().await.unwrap_err();
+    assert!(format!("{err:#}").contains("last_raw_response"), "{err:#}");
+    assert_eq!(
+        client.last_raw_response().as_deref(),
+        Some(r#"{"devices": "not a list"}"#)
+    );
+    // nothing else is kept by default
+    assert!(client.raw_frames().is_empty());
+
+    let client = canned("{}").retain_frames(2).build().unwrap();
+    for _ in 0..3 {
+        let _: serde_json::Value = client
+            .command_void(neohub::commands::GET_SYSTEM)
+            .await
+            .unwrap();
+    }
+    assert_eq!(client.raw_frames(), ["{}", "{}"]);
+}
+
+#[tokio::test]
+async fn store_if_changed() {
+    let profiles = format!(r#"{{"Winter": {}}}"#, include_str!("profile-1.json"));
+    let (client, records) = recording_client(canned(profiles).generation(Generation::Second));
+    let stored = || {
+        records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|message| message.contains("STORE_PROFILE"))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    let mut profile: neohub::Profile =
+        serde_json::from_str(include_str!("profile-1.json")).unwrap();
+    profile.profile_id = 0;
+    assert!(client.store_if_changed(&profile).await.unwrap().is_empty());
+    assert!(stored().is_empty());
+
+    profile.info.monday.wake.time = "07:00".to_string();
+    let diff = client.store_if_changed(&profile).await.unwrap();
+    assert_eq!(diff.changes.len(), 1);
+    assert!(stored()[0].starts_with("{'STORE_PROFILE2':{'ID':1,"));
+
+    profile.name = "Spring".to_string();
+    assert!(client.store_if_changed(&profile).await.unwrap().created);
+    assert!(stored()[1].starts_with("{'STORE_PROFILE2':{'info':"));
+}
+
+#[tokio::test]
+async fn poller() {
+    // the stats' clocks tick, then a window opens
+    let polls = Arc::new(AtomicUsize::new(0));
+    let live_data = move || {
+        let poll = polls.fetch_add(1, Ordering::SeqCst);
+        let mut live_data: serde_json::Value =
+            serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+        live_data["devices"][0]["TIME"] = format!("20:{:02}", 43 + poll).into();
+        live_data["devices"][0]["WRITE_COUNT"] = poll.into();
+        live_data["devices"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({
+                "ZONE_NAME": "Back Door",
+                "DEVICE_ID": 7,
+                "WINDOW_OPEN": poll >= 2,
+                "LOW_BATTERY": false,
+                "OFFLINE": false,
+            }));
+        live_data.to_string()
+    };
+    let live_data = Arc::new(live_data);
+    let client = Client::builder("wss://127.0.0.1:1", "token")
+        .transport(move || {
+            let live_data = live_data.clone();
+            async move { Ok(Memory::hub(move |_| live_data())) }
+        })
+        .generation(Generation::Second)
+        .build()
+        .unwrap();
+    let client = Arc::new(client);
+    assert!(neohub::Poller::spawn(client.clone(), Duration::ZERO).is_err());
+    let poller = neohub::Poller::spawn(client, Duration::from_millis(20)).unwrap();
+    let mut first = poller.subscribe();
+    let mut second = poller.subscribe();
+
+    let snapshot = first.recv().await.unwrap();
+    assert_eq!(snapshot.changed.len(), 7);
+    assert_eq!(second.recv().await.unwrap().live_data, snapshot.live_data);
+    // only the clock changed
+    assert!(first.recv().await.unwrap().changed.is_empty());
+    assert_eq!(first.recv().await.unwrap().changed, ["Back Door"]);
+    assert!(poller.latest().is_some());
+}
+
+#[tokio::test]
+async fn alerts_from_poller() {
+    use neohub::alerts::{AlertState, Alerts, Rule};
+
+    // the office goes offline after the first poll
+    let polls = Arc::new(AtomicUsize::new(0));
+    let live_data = move || {
+        let mut live_data: serde_json::Value =
+            serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+        live_data["devices"][0]["OFFLINE"] = (polls.fetch_add(1, Ordering::SeqCst) >= 1).into();
+        live_data.to_string()
+    };
+    let live_data = Arc::new(live_data);
+    let client = Client::builder("wss://127.0.0.1:1", "token")
+        .transport(move || {
+            let live_data = live_data.clone();
+            async move { Ok(Memory::hub(move |_| live_data())) }
+        })
+        .generation(Generation::Second)
+        .build()
+        .unwrap();
+    let poller = neohub::Poller::spawn(client, Duration::from_millis(20)).unwrap();
+    let (mut alerts, _task) = Alerts::new()
+        .rule(Rule::offline(Duration::ZERO))
+        .spawn(&poller);
+
+    let alert = alerts.recv().await.unwrap();
+    assert_eq!(alert.zone, "Office");
+    assert_eq!(alert.state, AlertState::Raised);
+    drop(poller);
+    assert!(alerts.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn transport() {
+    let connects = Arc::new(AtomicUsize::new(0));
+    let counter = connects.clone();
+    let client = Client::builder("wss://127.0.0.1:1", "token")
+        .transport(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Ok(Memory::hub(|command| format!(r#"{{"sent": "{command}"}}"#))) }
+        })
+        .build()
+        .unwrap();
+    let (device_id, resp) = client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    assert_eq!(device_id, "hub");
+    assert_eq!(resp, r#"{"sent": "{'FIRMWARE':0}"}"#);
+    client.ping().await.unwrap();
+
+    assert!(client.raw_message("{'BYE':0}").await.is_err());
+    client.raw_message("{'FIRMWARE':0}").await.unwrap();
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+}
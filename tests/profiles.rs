@@ -0,0 +1,213 @@
+use neohub::Profile;
+
+fn profile() -> Profile {
+    serde_json::from_str(include_str!("profile-1.json")).unwrap()
+}
+
+#[test]
+fn file_round_trip() {
+    let path = std::env::temp_dir().join(format!("neohub-profile-{}.json", std::process::id()));
+    let original = profile();
+    original.to_file(&path).unwrap();
+    let loaded = Profile::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.name, "Winter");
+    assert_eq!(loaded.profile_id, 0);
+    assert_eq!(
+        serde_json::to_value(&loaded.info).unwrap(),
+        serde_json::to_value(&original.info).unwrap()
+    );
+}
+
+#[test]
+fn file_out_of_order() {
+    let path = std::env::temp_dir().join(format!("neohub-bad-{}.json", std::process::id()));
+    let mut profile = profile();
+    profile.info.monday.leave.time = "05:00".to_string();
+    profile.to_file(&path).unwrap();
+    let loaded = Profile::from_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(loaded.is_err());
+}
+
+#[test]
+fn setpoints() {
+    use neohub::{Day, Event, ScheduleFormat};
+
+    let profile = profile();
+    let at = profile
+        .setpoints_at(ScheduleFormat::SevenDay, Day::Tuesday, "12:00")
+        .unwrap();
+    assert_eq!(at.current.event, Event::Leave);
+    assert_eq!(at.next.event, Event::Return);
+    assert_eq!(at.next.time, "17:30");
+
+    // before wake on a monday, sunday night's sleep is still going
+    let at = profile
+        .setpoints_at(ScheduleFormat::SevenDay, Day::Monday, "03:00")
+        .unwrap();
+    assert_eq!(
+        (at.current.day, at.current.event),
+        (Day::Sunday, Event::Sleep)
+    );
+    assert_eq!(at.current.time, "23:00");
+    assert_eq!((at.next.day, at.next.event), (Day::Monday, Event::Wake));
+
+    // after friday's sleep, saturday's (weekend) wake is next
+    let at = profile
+        .setpoints_at(ScheduleFormat::WeekdaysWeekend, Day::Friday, "23:59")
+        .unwrap();
+    assert_eq!(
+        (at.next.day, at.next.time.as_str()),
+        (Day::Saturday, "08:00")
+    );
+}
+
+#[test]
+fn timeline() {
+    use neohub::{Day, Event, ScheduleFormat};
+
+    let week = profile().timeline(ScheduleFormat::SevenDay).unwrap();
+    assert_eq!(week.len(), 7);
+    assert!(week.iter().all(|day| {
+        day.segments.first().unwrap().minutes.start == 0
+            && day.segments.last().unwrap().minutes.end == 24 * 60
+            && day
+                .segments
+                .windows(2)
+                .all(|w| w[0].minutes.end == w[1].minutes.start)
+    }));
+
+    let monday = &week[0];
+    assert_eq!(monday.day, Day::Monday);
+    assert_eq!(monday.segments.len(), 5);
+    assert_eq!(monday.segments[0].event, Event::Sleep);
+    assert_eq!(monday.segments[1].minutes, 390..510);
+    assert_eq!(monday.segments[1].temperature, 21.0);
+}
+
+#[test]
+fn validate() {
+    use neohub::{Day, Event, ProfileProblem, ScheduleFormat, TemperatureUnit};
+
+    let mut profile = profile();
+    profile
+        .validate(ScheduleFormat::WeekdaysWeekend, TemperatureUnit::Celsius)
+        .unwrap();
+    // the hub's range is in its own unit
+    assert!(profile
+        .validate(ScheduleFormat::SevenDay, TemperatureUnit::Fahrenheit)
+        .is_err());
+    let problems = profile
+        .validate(ScheduleFormat::SameEveryDay, TemperatureUnit::Celsius)
+        .unwrap_err()
+        .problems;
+    assert_eq!(
+        problems,
+        [
+            ProfileProblem::Ignored {
+                day: Day::Saturday,
+                uses: Day::Monday
+            },
+            ProfileProblem::Ignored {
+                day: Day::Sunday,
+                uses: Day::Monday
+            },
+        ]
+    );
+
+    profile.info.tuesday.leave.time = "05:00".to_string();
+    profile.info.tuesday.sleep.temperature = 40.0;
+    profile.info.tuesday.wake.temperature = 20.2;
+    let err = profile
+        .validate(ScheduleFormat::SevenDay, TemperatureUnit::Celsius)
+        .unwrap_err();
+    assert_eq!(
+        err.problems,
+        [
+            ProfileProblem::OutOfOrder {
+                day: Day::Tuesday,
+                event: Event::Leave,
+                time: "05:00".to_string(),
+                previous: "06:30".to_string(),
+            },
+            ProfileProblem::BadStep {
+                day: Day::Tuesday,
+                event: Event::Wake,
+                temperature: 20.2,
+                step: 0.5,
+            },
+            ProfileProblem::OutOfRange {
+                day: Day::Tuesday,
+                event: Event::Sleep,
+                temperature: 40.0,
+                min: 5.0,
+                max: 35.0,
+            },
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid profile \"Winter\": Tuesday Leave at 05:00 is not after the previous event, \
+         at 06:30; Tuesday Wake temperature 20.2 isn't a multiple of 0.5; Tuesday Sleep \
+         temperature 40 is outside 5-35"
+    );
+}
+
+#[test]
+fn diff() {
+    use neohub::{diff_profiles, Day, Event, ProfileChange};
+
+    let hub = profile();
+    let mut local = profile();
+    assert!(diff_profiles(&local, &hub).is_empty());
+
+    local.name = "Winter 2".to_string();
+    local.info.friday.sleep.time = "23:30".to_string();
+    local.info.friday.sleep.temperature = 16.0;
+    let diff = diff_profiles(&local, &hub);
+    assert_eq!(
+        diff.changes[1],
+        ProfileChange::Time {
+            day: Day::Friday,
+            event: Event::Sleep,
+            from: "22:30".to_string(),
+            to: "23:30".to_string(),
+        }
+    );
+    assert_eq!(
+        diff.to_string(),
+        "name: \"Winter\" -> \"Winter 2\"\n\
+         Friday Sleep time: 22:30 -> 23:30\n\
+         Friday Sleep temperature: 17 -> 16\n"
+    );
+}
+
+#[test]
+fn quick_set() {
+    use neohub::{Day, Event, ScheduleFormat, TemperatureUnit};
+
+    let mut profile = profile();
+    profile
+        .apply_to_weekdays(Event::Wake, "06:00", 20.0)
+        .unwrap();
+    profile
+        .set_event(Day::Sunday, Event::Sleep, "23:30", 16.0)
+        .unwrap();
+    assert!(profile
+        .set_event(Day::Monday, Event::Leave, "8.30", 17.0)
+        .is_err());
+
+    for day in Day::WEEKDAYS {
+        assert_eq!(profile.info.day(day).wake.time, "06:00");
+        assert_eq!(profile.info.day(day).wake.temperature, 20.0);
+    }
+    assert_eq!(profile.info.saturday.wake.time, "08:00");
+    assert_eq!(profile.info.sunday.sleep.temperature, 16.0);
+    // extra fields the hub sent are kept
+    assert_eq!(profile.info.monday.wake.extra.len(), 2);
+    profile
+        .validate(ScheduleFormat::SevenDay, TemperatureUnit::Celsius)
+        .unwrap();
+}
@@ -0,0 +1,57 @@
+use neohub::protocol::{
+    command_name, decode_frame, decode_request, decode_response, encode, encode_error,
+    encode_response, encode_void, Frame, Session,
+};
+use neohub::HubRejected;
+use serde_json::{json, Value};
+
+#[test]
+fn request_round_trip() {
+    let session = Session::new("token");
+    let first = session.request(&encode_void("FIRMWARE")).unwrap();
+    let second = session
+        .request(&encode("SET_TEMP", (20, ["Office"])).unwrap())
+        .unwrap();
+    assert_eq!(second.command_id, first.command_id + 1);
+
+    let queue = decode_request(&second.frame).unwrap();
+    assert_eq!(queue.token, "token");
+    assert_eq!(queue.commands[0].command, "{'SET_TEMP':[20,['Office']]}");
+    assert_eq!(queue.commands[0].command_id, json!(second.command_id));
+    assert_eq!(command_name(&queue.commands[0].command), Some("SET_TEMP"));
+    assert!(decode_request("{}").is_err());
+}
+
+#[test]
+fn frames() {
+    let response = encode_response(&json!(3), "hub", r#"{"result": "temperature was set"}"#);
+    match decode_frame(response.as_bytes()) {
+        Frame::Response(resp) => {
+            assert_eq!(resp.command_id, 3);
+            let ack: Value = decode_response("SET_TEMP", &resp.response).unwrap();
+            assert_eq!(ack["result"], "temperature was set");
+        }
+        frame => panic!("{:?}", frame),
+    }
+    assert_eq!(
+        decode_frame(encode_error("Invalid token").as_bytes()),
+        Frame::Error("Invalid token".to_string())
+    );
+    let unsolicited = json!({
+        "command_id": 1,
+        "device_id": "hub",
+        "message_type": "hm_set_device_data",
+        "response": "{}",
+    });
+    assert!(matches!(
+        decode_frame(unsolicited.to_string().as_bytes()),
+        Frame::Unsolicited(_)
+    ));
+    assert!(matches!(decode_frame(b"nonsense"), Frame::Unreadable(_)));
+}
+
+#[test]
+fn rejected() {
+    let err = decode_response::<Value>("SET_TEMP", r#"{"error": "Invalid argument"}"#).unwrap_err();
+    assert!(err.downcast_ref::<HubRejected>().is_some());
+}
@@ -0,0 +1,107 @@
+use neohub::{Action, Day, Recurrence, Scheduler, TimeZone};
+
+// 2024-01-01T00:00:00Z, a Monday
+const NEW_YEAR: i64 = 1_704_067_200;
+const HOUR: i64 = 3600;
+
+fn scheduler() -> Scheduler {
+    let mut scheduler = Scheduler::new();
+    scheduler
+        .add(
+            "hall night",
+            Recurrence::weekdays("23:00"),
+            Action::SetTemperature {
+                temperature: 16.0,
+                zones: vec!["Hall".to_string()],
+            },
+        )
+        .unwrap();
+    scheduler
+        .add(
+            "monthly standby",
+            Recurrence::monthly(1, "00:00"),
+            Action::AllStandby,
+        )
+        .unwrap();
+    scheduler
+}
+
+fn names(scheduler: &Scheduler, zone: TimeZone, after: i64, until: i64) -> Vec<&str> {
+    scheduler
+        .due(zone, after, until)
+        .into_iter()
+        .map(|job| job.name.as_str())
+        .collect()
+}
+
+#[test]
+fn due() {
+    let scheduler = scheduler();
+    let utc = TimeZone::UTC;
+    // the start of the range has already been handled
+    assert_eq!(
+        names(&scheduler, utc, NEW_YEAR, NEW_YEAR + 60),
+        Vec::<&str>::new()
+    );
+    assert_eq!(
+        names(&scheduler, utc, NEW_YEAR - 1, NEW_YEAR + 60),
+        ["monthly standby"]
+    );
+    assert_eq!(
+        names(&scheduler, utc, NEW_YEAR, NEW_YEAR + 24 * HOUR),
+        ["hall night"]
+    );
+    // 23:00 in UTC+1 is 22:00 UTC
+    let zone = TimeZone {
+        offset_seconds: 3600,
+    };
+    let at = NEW_YEAR + 22 * HOUR;
+    assert_eq!(names(&scheduler, zone, at - 1, at), ["hall night"]);
+    // Saturday and Sunday
+    let weekend = NEW_YEAR + 5 * 24 * HOUR;
+    assert!(names(&scheduler, utc, weekend, weekend + 48 * HOUR).is_empty());
+}
+
+#[test]
+fn invalid_jobs() {
+    let mut scheduler = scheduler();
+    assert!(scheduler
+        .add("hall night", Recurrence::daily("07:00"), Action::AllResume)
+        .is_err());
+    assert!(scheduler
+        .add("late", Recurrence::daily("25:00"), Action::AllResume)
+        .is_err());
+    assert!(scheduler
+        .add("never", Recurrence::on([], "07:00"), Action::AllResume)
+        .is_err());
+    assert!(scheduler
+        .add(
+            "day zero",
+            Recurrence::monthly(0, "07:00"),
+            Action::AllResume
+        )
+        .is_err());
+    assert!(scheduler.remove("hall night"));
+    assert!(!scheduler.remove("hall night"));
+    assert_eq!(scheduler.jobs().len(), 1);
+}
+
+#[test]
+fn file_round_trip() {
+    let path = std::env::temp_dir().join(format!("neohub-scheduler-{}.json", std::process::id()));
+    let mut original = scheduler();
+    original
+        .add(
+            "weekend lie-in",
+            Recurrence::on([Day::Saturday, Day::Sunday], "09:00"),
+            Action::RunProfile {
+                profile: "Weekend".to_string(),
+                zones: vec!["Bedroom".to_string()],
+            },
+        )
+        .unwrap();
+    original.save(&path).unwrap();
+    let loaded = Scheduler::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, original);
+}
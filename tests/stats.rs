@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use neohub::stats::DutyCycles;
+use neohub::LiveData;
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+#[test]
+fn duty_cycles() {
+    let mut live_data: serde_json::Value =
+        serde_json::from_str(include_str!("live-data-1.json")).unwrap();
+    let mut stats = DutyCycles::new([2 * MINUTE, 60 * MINUTE]).max_gap(Duration::from_secs(90));
+    let start = 1663101780;
+    // the last snapshot is after a long gap, most of which isn't counted
+    for (offset, heat_on) in [
+        (0, false),
+        (60, true),
+        (120, true),
+        (180, false),
+        (580, false),
+    ] {
+        live_data["HUB_TIME"] = (start + offset).into();
+        live_data["devices"][0]["HEAT_ON"] = heat_on.into();
+        let snapshot: LiveData = serde_json::from_value(live_data.clone()).unwrap();
+        stats.observe(&snapshot);
+        if offset == 180 {
+            let office = stats.zone("Office").unwrap();
+            assert_eq!(office[0].heat_on, 2 * MINUTE);
+            assert_eq!(office[0].fraction(), Some(1.0));
+            assert_eq!(office[1].observed, 3 * MINUTE);
+        }
+    }
+
+    let office = stats.zone("Office").unwrap();
+    assert_eq!(office[0].observed, Duration::ZERO);
+    assert_eq!(office[0].fraction(), None);
+    assert_eq!(office[1].heat_on, 2 * MINUTE);
+    assert_eq!(office[1].observed, Duration::from_secs(270));
+    assert_eq!(stats.total_heat_on("Office"), Some(2 * MINUTE));
+    assert_eq!(stats.report().len(), 6);
+    assert!(stats.zone("Nowhere").is_none());
+}